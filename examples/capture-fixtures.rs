@@ -0,0 +1,47 @@
+//! Saves anonymised copies of the modem pages to `tests/fixtures/corpus/<name>`.
+
+use callog_bot::timm::capture::{self, PAGES};
+use callog_bot::timm::modem::Modem;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process;
+
+#[tokio::main]
+async fn main() {
+    let name = match env::args().nth(1) {
+        Some(name) => name,
+        None => {
+            eprintln!("Usage: capture-fixtures <name>");
+            process::exit(1);
+        }
+    };
+
+    let modem = Modem::from_env();
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures/corpus")
+        .join(&name);
+
+    if let Err(error) = fs::create_dir_all(&dir) {
+        eprintln!("Couldn't create {}: {}", dir.display(), error);
+        process::exit(1);
+    }
+
+    for page in PAGES {
+        let html = match modem.page(page).await {
+            Some(html) => html,
+            None => {
+                eprintln!("Couldn't download {} from {}", page, modem.url);
+                process::exit(1);
+            }
+        };
+
+        let path = dir.join(page);
+        if let Err(error) = fs::write(&path, capture::anonymise(&html)) {
+            eprintln!("Couldn't write {}: {}", path.display(), error);
+            process::exit(1);
+        }
+
+        println!("Saved {}", path.display());
+    }
+}
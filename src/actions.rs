@@ -0,0 +1,133 @@
+use std::fmt::{Display, Formatter};
+use teloxide::types::{InlineKeyboardButton, InlineKeyboardMarkup};
+
+// Telegram refuses callback data longer than this
+const MAX_CALLBACK_DATA: usize = 64;
+
+const SAVE_PROMPT: &str = "What name should I save for ";
+
+/// The buttons attached to a new call notification.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum CallAction {
+    Save(String),
+    Spam(String),
+    History(String),
+    Copy(String),
+}
+
+impl Display for CallAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallAction::Save(number) => write!(f, "save:{}", number),
+            CallAction::Spam(number) => write!(f, "spam:{}", number),
+            CallAction::History(number) => write!(f, "history:{}", number),
+            CallAction::Copy(number) => write!(f, "copy:{}", number),
+        }
+    }
+}
+
+impl TryFrom<&str> for CallAction {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (action, number) = value.split_once(':').ok_or(())?;
+
+        if number.is_empty() {
+            return Err(());
+        }
+
+        let number = number.to_string();
+
+        match action {
+            "save" => Ok(CallAction::Save(number)),
+            "spam" => Ok(CallAction::Spam(number)),
+            "history" => Ok(CallAction::History(number)),
+            "copy" => Ok(CallAction::Copy(number)),
+            _ => Err(()),
+        }
+    }
+}
+
+pub fn call_keyboard(who: &str) -> Option<InlineKeyboardMarkup> {
+    let history = CallAction::History(who.to_string()).to_string();
+
+    // the longest callback data has to fit, otherwise Telegram rejects the whole message
+    if history.len() > MAX_CALLBACK_DATA {
+        debug!("Caller {} is too long for a keyboard", who);
+        return None;
+    }
+
+    Some(InlineKeyboardMarkup::new(vec![
+        vec![
+            InlineKeyboardButton::callback(
+                "💾 Save contact",
+                CallAction::Save(who.to_string()).to_string(),
+            ),
+            InlineKeyboardButton::callback(
+                "🚫 Mark as spam",
+                CallAction::Spam(who.to_string()).to_string(),
+            ),
+        ],
+        vec![
+            InlineKeyboardButton::callback("🕘 Previous calls", history),
+            InlineKeyboardButton::callback(
+                "📋 Copy number",
+                CallAction::Copy(who.to_string()).to_string(),
+            ),
+        ],
+    ]))
+}
+
+pub fn save_prompt(number: &str) -> String {
+    format!("{}{}?", SAVE_PROMPT, number)
+}
+
+/// Finds the number in a prompt sent by `save_prompt`, so that a reply to it can be saved.
+pub fn parse_save_prompt(text: &str) -> Option<&str> {
+    text.strip_prefix(SAVE_PROMPT)?
+        .strip_suffix('?')
+        .filter(|number| !number.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_action_round_trip() {
+        let actions = vec![
+            CallAction::Save("0612345678".to_string()),
+            CallAction::Spam("0612345678".to_string()),
+            CallAction::History("0612345678".to_string()),
+            CallAction::Copy("0612345678".to_string()),
+        ];
+
+        for action in actions {
+            assert_eq!(
+                CallAction::try_from(action.to_string().as_str()),
+                Ok(action)
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_action() {
+        assert_eq!(CallAction::try_from("call:0612345678"), Err(()));
+        assert_eq!(CallAction::try_from("save:"), Err(()));
+        assert_eq!(CallAction::try_from("save"), Err(()));
+    }
+
+    #[test]
+    fn test_long_caller_has_no_keyboard() {
+        assert!(call_keyboard("0612345678").is_some());
+        assert!(call_keyboard(&"9".repeat(60)).is_none());
+    }
+
+    #[test]
+    fn test_save_prompt() {
+        let prompt = save_prompt("0612345678");
+
+        assert_eq!(parse_save_prompt(&prompt), Some("0612345678"));
+        assert_eq!(parse_save_prompt("Some other message?"), None);
+    }
+}
@@ -0,0 +1,222 @@
+//! Queries the modem from the shell, for cron jobs and scripts, without a Telegram token.
+//!
+//!     callog calls [--since 1d] [--json|--csv|--ics]
+//!     callog stats [--json|--csv]
+//!     callog reboot --yes
+//!     callog watch [--every 30s] [--json|--csv]
+
+use callog_bot::contacts::Contacts;
+use callog_bot::export::{self, Row};
+use callog_bot::history;
+use callog_bot::schedule;
+use callog_bot::timm::calls::{self, PhoneCall};
+use callog_bot::timm::modem::Modem;
+use callog_bot::timm::stats::{self, LineStats};
+use callog_bot::timm::tools;
+use chrono::Duration;
+use std::collections::HashSet;
+use std::env;
+use std::process;
+use tokio::time::sleep;
+
+const USAGE: &str = "Usage:
+  callog calls [--since 1d] [--json|--csv|--ics]
+  callog stats [--json|--csv]
+  callog reboot --yes
+  callog watch [--every 30s] [--json|--csv]";
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+enum Format {
+    Table,
+    Json,
+    Csv,
+    Ics,
+}
+
+struct Options {
+    command: String,
+    format: Format,
+    since: Option<Duration>,
+    every: Duration,
+    yes: bool,
+}
+
+fn parse_options(args: &[String]) -> Option<Options> {
+    let mut args = args.iter();
+    let mut options = Options {
+        command: args.next()?.clone(),
+        format: Format::Table,
+        since: None,
+        every: Duration::seconds(30),
+        yes: false,
+    };
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.format = Format::Json,
+            "--csv" => options.format = Format::Csv,
+            "--ics" => options.format = Format::Ics,
+            "--yes" => options.yes = true,
+            "--since" => options.since = Some(schedule::parse_duration(args.next()?)?),
+            "--every" => options.every = schedule::parse_duration(args.next()?)?,
+            _ => return None,
+        }
+    }
+
+    Some(options)
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn call_row(format: Format, phone_call: &PhoneCall, contacts: &Contacts) -> String {
+    match format {
+        Format::Table => {
+            let row = Row::new(phone_call, contacts);
+
+            format!(
+                "{}  {:<16}  {:<20}  {}",
+                row.when.format("%Y-%m-%d %H:%M:%S"),
+                row.number,
+                row.name.unwrap_or_default(),
+                if row.missed { "missed" } else { "" }
+            )
+            .trim_end()
+            .to_string()
+        }
+        Format::Csv => export::csv_row(phone_call, contacts),
+        _ => serde_json::to_string(&Row::new(phone_call, contacts)).unwrap_or_default(),
+    }
+}
+
+fn print_calls(format: Format, phone_calls: &[PhoneCall], contacts: &Contacts) {
+    match format {
+        Format::Table => phone_calls
+            .iter()
+            .for_each(|phone_call| println!("{}", call_row(format, phone_call, contacts))),
+        Format::Csv => print!("{}", export::csv(phone_calls, contacts)),
+        Format::Json => print!("{}", export::json(phone_calls, contacts)),
+        Format::Ics => print!("{}", export::ics(phone_calls, contacts)),
+    }
+}
+
+fn print_stats(format: Format, stats: &LineStats) {
+    match format {
+        Format::Json => match serde_json::to_string_pretty(stats) {
+            Ok(json) => println!("{}", json),
+            Err(error) => fail(&format!("Couldn't write the stats: {}", error)),
+        },
+        Format::Ics => fail("Stats are shown as a table, CSV or JSON."),
+        Format::Csv => {
+            println!("ip,download,upload,speed");
+            println!(
+                "{},{},{},{:?}",
+                stats.ip, stats.download, stats.upload, stats.speed
+            );
+        }
+        Format::Table => {
+            println!("IP        {}", stats.ip);
+            println!("Download  {} kbps", stats.download);
+            println!("Upload    {} kbps", stats.upload);
+            println!("Speed     {:?}", stats.speed);
+        }
+    }
+}
+
+async fn list_calls(modem: &Modem, options: &Options) {
+    let mut phone_calls = match calls::download_calls(modem).await {
+        Some(phone_calls) => phone_calls,
+        None => fail(&format!("Couldn't get the calls from {}", modem.url)),
+    };
+
+    // longer than chrono can go back is the same as no limit
+    if let Some(since) = options
+        .since
+        .and_then(|since| history::now().checked_sub_signed(since))
+    {
+        phone_calls.retain(|phone_call| phone_call.when >= since);
+    }
+
+    // oldest first, as in a log
+    phone_calls.reverse();
+    print_calls(options.format, &phone_calls, &Contacts::load());
+}
+
+async fn show_stats(modem: &Modem, options: &Options) {
+    match stats::download_stats(modem).await {
+        Some(stats) => print_stats(options.format, &stats),
+        None => fail(&format!("Couldn't get the stats from {}", modem.url)),
+    }
+}
+
+async fn reboot(modem: &Modem, options: &Options) {
+    if !options.yes {
+        fail("This reboots the modem, dropping calls and the connection: add --yes to go ahead.");
+    }
+
+    match tools::reboot(modem).await {
+        Some(resp) if resp.status().is_success() => println!("Rebooting {}", modem.url),
+        _ => fail(&format!("Couldn't reboot {}", modem.url)),
+    }
+}
+
+/// Prints calls as they arrive, one line each, until stopped.
+async fn watch(modem: &Modem, options: &Options) {
+    let mut seen: Option<HashSet<(String, chrono::NaiveDateTime)>> = None;
+    let contacts = Contacts::load();
+
+    match options.format {
+        Format::Csv => println!("{}", export::CSV_HEADER),
+        Format::Ics => fail("Calls are watched as a table, CSV or JSON lines."),
+        _ => {}
+    }
+
+    loop {
+        if let Some(mut phone_calls) = calls::download_calls(modem).await {
+            phone_calls.reverse();
+
+            // the calls already there when watching starts aren't printed
+            if let Some(seen) = &mut seen {
+                for phone_call in &phone_calls {
+                    if seen.insert((phone_call.who.key(), phone_call.when)) {
+                        println!("{}", call_row(options.format, phone_call, &contacts));
+                    }
+                }
+            } else {
+                seen = Some(
+                    phone_calls
+                        .iter()
+                        .map(|phone_call| (phone_call.who.key(), phone_call.when))
+                        .collect(),
+                );
+            }
+        } else {
+            eprintln!("Couldn't get the calls from {}", modem.url);
+        }
+
+        sleep(options.every.to_std().unwrap_or_default()).await;
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    dotenv::dotenv().ok();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let options = match parse_options(&args) {
+        Some(options) => options,
+        None => fail(USAGE),
+    };
+
+    let modem = Modem::from_env();
+
+    match options.command.as_str() {
+        "calls" => list_calls(&modem, &options).await,
+        "stats" => show_stats(&modem, &options).await,
+        "reboot" => reboot(&modem, &options).await,
+        "watch" => watch(&modem, &options).await,
+        _ => fail(USAGE),
+    }
+}
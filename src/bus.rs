@@ -0,0 +1,111 @@
+use crate::event::Event;
+use tokio::sync::broadcast::{
+    self,
+    error::{RecvError, TryRecvError},
+    Receiver, Sender,
+};
+
+// how many events a slow subscriber can fall behind before missing some
+const CAPACITY: usize = 256;
+
+/// Carries what the monitors notice to every subscriber, the Telegram alerts being one of them.
+#[derive(Clone, Debug)]
+pub struct Bus {
+    sender: Sender<Event>,
+}
+
+impl Default for Bus {
+    fn default() -> Self {
+        Bus::new()
+    }
+}
+
+impl Bus {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(CAPACITY);
+
+        Bus { sender }
+    }
+
+    pub fn publish(&self, event: Event) {
+        debug!("Publishing {:?}", event.kind());
+
+        // with nobody subscribed the event is simply dropped
+        if self.sender.send(event).is_err() {
+            debug!("Nobody is listening for events");
+        }
+    }
+
+    /// Receives the events published from now on.
+    pub fn subscribe(&self) -> Receiver<Event> {
+        self.sender.subscribe()
+    }
+}
+
+/// Waits for the next event, skipping any the subscriber fell too far behind to get; None
+/// once the bus is gone.
+pub async fn next(receiver: &mut Receiver<Event>) -> Option<Event> {
+    loop {
+        match receiver.recv().await {
+            Ok(event) => return Some(event),
+            Err(RecvError::Lagged(missed)) => warn!("Missed {} events", missed),
+            Err(RecvError::Closed) => return None,
+        }
+    }
+}
+
+/// The next event if one is already waiting.
+pub fn try_next(receiver: &mut Receiver<Event>) -> Option<Event> {
+    loop {
+        match receiver.try_recv() {
+            Ok(event) => return Some(event),
+            Err(TryRecvError::Lagged(missed)) => warn!("Missed {} events", missed),
+            Err(_) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn unreachable(minute: u32) -> Event {
+        Event::ModemUnreachable {
+            at: NaiveDate::from_ymd_opt(2026, 10, 5)
+                .unwrap()
+                .and_hms_opt(9, minute, 0)
+                .unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_every_subscriber_gets_every_event() {
+        let bus = Bus::new();
+        bus.publish(unreachable(0));
+
+        let mut first = bus.subscribe();
+        let mut second = bus.subscribe();
+        bus.publish(unreachable(1));
+        bus.publish(unreachable(2));
+
+        for receiver in [&mut first, &mut second] {
+            assert_eq!(try_next(receiver), Some(unreachable(1)));
+            assert_eq!(try_next(receiver), Some(unreachable(2)));
+            assert_eq!(try_next(receiver), None);
+        }
+    }
+
+    #[test]
+    fn test_slow_subscribers_skip_ahead() {
+        let bus = Bus::new();
+        let mut receiver = bus.subscribe();
+
+        for minute in 0..CAPACITY as u32 + 2 {
+            bus.publish(unreachable(minute % 60));
+        }
+
+        assert_eq!(try_next(&mut receiver), Some(unreachable(2)));
+    }
+}
@@ -0,0 +1,331 @@
+use crate::contacts::Contacts;
+use crate::timm::caller::CallerId;
+use crate::timm::calls::PhoneCall;
+use chrono::{Datelike, Duration, NaiveDateTime, Timelike, Weekday};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use teloxide::utils::html;
+
+const TOP_CALLERS: usize = 5;
+
+// calls from the same number this close together hint it's urgent
+const REPEAT_MINUTES: i64 = 10;
+
+const WEEKDAYS: [Weekday; 7] = [
+    Weekday::Mon,
+    Weekday::Tue,
+    Weekday::Wed,
+    Weekday::Thu,
+    Weekday::Fri,
+    Weekday::Sat,
+    Weekday::Sun,
+];
+
+/// A caller who rang several times within minutes.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Repeat {
+    pub caller: CallerId,
+    pub calls: usize,
+    pub last: NaiveDateTime,
+}
+
+/// Who called and when, over a period.
+#[derive(PartialEq, Eq, Debug)]
+pub struct CallStats {
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub calls: usize,
+    pub missed: usize,
+    pub top_callers: Vec<(CallerId, usize)>,
+    /// Calls by weekday, from Monday, and by hour.
+    pub heatmap: [[usize; 24]; 7],
+    /// The numbers that called for the first time in the week up to `to`.
+    pub first_time: Vec<CallerId>,
+    pub repeats: Vec<Repeat>,
+}
+
+impl CallStats {
+    /// Builds the statistics for the calls between `from` and `to`, out of all the calls
+    /// known so first-time callers can be told apart.
+    pub fn build(phone_calls: &[PhoneCall], from: NaiveDateTime, to: NaiveDateTime) -> Self {
+        let mut phone_calls: Vec<&PhoneCall> =
+            phone_calls.iter().filter(|call| call.when < to).collect();
+        phone_calls.sort_by_key(|phone_call| phone_call.when);
+
+        let week = to - Duration::weeks(1);
+        let mut first_seen: BTreeMap<String, (CallerId, NaiveDateTime)> = BTreeMap::new();
+        for phone_call in phone_calls
+            .iter()
+            .filter(|call| call.who.number().is_some())
+        {
+            first_seen
+                .entry(phone_call.who.key())
+                .or_insert_with(|| (phone_call.who.clone(), phone_call.when));
+        }
+        let mut first_time: Vec<(CallerId, NaiveDateTime)> = first_seen
+            .into_values()
+            .filter(|(_, when)| *when >= week)
+            .collect();
+        first_time.sort_by_key(|(_, when)| *when);
+
+        let period: Vec<&PhoneCall> = phone_calls
+            .into_iter()
+            .filter(|phone_call| phone_call.when >= from)
+            .collect();
+
+        let mut callers: BTreeMap<String, (CallerId, usize)> = BTreeMap::new();
+        let mut heatmap = [[0; 24]; 7];
+        let mut times: BTreeMap<String, (CallerId, Vec<NaiveDateTime>)> = BTreeMap::new();
+
+        for phone_call in &period {
+            let key = phone_call.who.key();
+
+            callers
+                .entry(key.clone())
+                .or_insert_with(|| (phone_call.who.clone(), 0))
+                .1 += 1;
+            heatmap[phone_call.when.weekday().num_days_from_monday() as usize]
+                [phone_call.when.hour() as usize] += 1;
+
+            // withheld numbers could be anybody, so they don't count as repeats
+            if phone_call.who.number().is_some() {
+                times
+                    .entry(key)
+                    .or_insert_with(|| (phone_call.who.clone(), Vec::new()))
+                    .1
+                    .push(phone_call.when);
+            }
+        }
+
+        let mut top_callers: Vec<(CallerId, usize)> = callers.into_values().collect();
+        top_callers.sort_by_key(|(_, count)| Reverse(*count));
+        top_callers.truncate(TOP_CALLERS);
+
+        let mut repeats: Vec<Repeat> = times
+            .into_values()
+            .flat_map(|(caller, times)| {
+                bursts(&times).into_iter().map(move |(calls, last)| Repeat {
+                    caller: caller.clone(),
+                    calls,
+                    last,
+                })
+            })
+            .collect();
+        repeats.sort_by_key(|repeat| Reverse(repeat.last));
+
+        CallStats {
+            from,
+            to,
+            calls: period.len(),
+            missed: period.iter().filter(|phone_call| phone_call.missed).count(),
+            top_callers,
+            heatmap,
+            first_time: first_time.into_iter().map(|(caller, _)| caller).collect(),
+            repeats,
+        }
+    }
+
+    /// The share of calls nobody answered, in percent.
+    pub fn missed_ratio(&self) -> Option<f64> {
+        (self.calls > 0).then(|| self.missed as f64 * 100.0 / self.calls as f64)
+    }
+
+    /// The heatmap as text, a row a weekday and a column an hour, darker for more calls.
+    pub fn heatmap_text(&self) -> String {
+        const SHADES: [char; 4] = ['░', '▒', '▓', '█'];
+
+        let most = self.heatmap.iter().flatten().copied().max().unwrap_or(0);
+        let mut lines = vec![format!("    {:<6}{:<6}{:<6}{}", 0, 6, 12, 18)];
+
+        for (weekday, hours) in WEEKDAYS.iter().zip(self.heatmap.iter()) {
+            let row: String = hours
+                .iter()
+                .map(|count| match count {
+                    0 => '·',
+                    count => SHADES[(count * SHADES.len()).div_ceil(most) - 1],
+                })
+                .collect();
+
+            lines.push(format!("{} {}", weekday, row));
+        }
+
+        lines.join("\n")
+    }
+
+    /// The statistics as HTML, for a Telegram message.
+    pub fn message(&self, contacts: &Contacts) -> String {
+        let name = |caller: &CallerId| {
+            html::escape(&match contacts.name(&caller.key()) {
+                Some(name) => format!("👤 {}", name),
+                None => caller.to_string(),
+            })
+        };
+
+        let mut lines = vec![format!(
+            "📊 Calls, {} → {}",
+            self.from.format("%-d %b"),
+            self.to.format("%-d %b")
+        )];
+
+        if self.calls == 0 {
+            lines.push("No calls.".to_string());
+            return lines.join("\n");
+        }
+
+        lines.push(format!(
+            "{} calls, {} missed ({:.0}%)",
+            self.calls,
+            self.missed,
+            self.missed_ratio().unwrap_or_default()
+        ));
+
+        lines.push("\nTop callers:".to_string());
+        for (caller, count) in &self.top_callers {
+            lines.push(format!("{} ×{}", name(caller), count));
+        }
+
+        lines.push(format!(
+            "\n<pre>{}</pre>",
+            html::escape(&self.heatmap_text())
+        ));
+
+        if !self.first_time.is_empty() {
+            let callers: Vec<String> = self.first_time.iter().map(name).collect();
+            lines.push(format!(
+                "🆕 First-time callers this week: {}",
+                callers.join(", ")
+            ));
+        }
+
+        for repeat in &self.repeats {
+            lines.push(format!(
+                "🔁 {} rang {} times within minutes, last at {}: maybe call back",
+                name(&repeat.caller),
+                repeat.calls,
+                repeat.last.format("%H:%M on %-d %b")
+            ));
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// Groups sorted call times that follow each other within minutes, giving how many calls
+/// and the last of each group of two or more.
+fn bursts(times: &[NaiveDateTime]) -> Vec<(usize, NaiveDateTime)> {
+    let mut bursts = Vec::new();
+    let mut count = 1;
+
+    for (index, when) in times.iter().enumerate() {
+        let next = times.get(index + 1);
+
+        if next.is_some_and(|next| *next - *when <= Duration::minutes(REPEAT_MINUTES)) {
+            count += 1;
+        } else {
+            if count > 1 {
+                bursts.push((count, *when));
+            }
+            count = 1;
+        }
+    }
+
+    bursts
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn call(number: &str, when: NaiveDateTime, missed: bool) -> PhoneCall {
+        PhoneCall {
+            who: CallerId::from(number),
+            when,
+            missed,
+        }
+    }
+
+    #[test]
+    fn test_build() {
+        let phone_calls = vec![
+            call("0612345678", at(1, 9, 0), false),
+            call("0612345678", at(12, 9, 30), false),
+            call("3331234567", at(13, 18, 0), true),
+            call("3331234567", at(13, 18, 4), true),
+            call("3331234567", at(13, 18, 9), false),
+            call("0698765432", at(14, 10, 0), true),
+        ];
+
+        let stats = CallStats::build(&phone_calls, at(12, 0, 0), at(15, 0, 0));
+
+        assert_eq!(stats.calls, 5);
+        assert_eq!(stats.missed, 3);
+        assert_eq!(stats.missed_ratio(), Some(60.0));
+        assert_eq!(stats.top_callers[0], (CallerId::from("3331234567"), 3));
+        // the 12th is a Monday
+        assert_eq!(stats.heatmap[0][9], 1);
+        assert_eq!(stats.heatmap[1][18], 3);
+        assert_eq!(
+            stats.first_time,
+            vec![CallerId::from("3331234567"), CallerId::from("0698765432")]
+        );
+        assert_eq!(
+            stats.repeats,
+            vec![Repeat {
+                caller: CallerId::from("3331234567"),
+                calls: 3,
+                last: at(13, 18, 9),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_bursts() {
+        let times = [
+            at(12, 9, 0),
+            at(12, 9, 5),
+            at(12, 11, 0),
+            at(12, 14, 0),
+            at(12, 14, 10),
+        ];
+
+        assert_eq!(bursts(&times), vec![(2, at(12, 9, 5)), (2, at(12, 14, 10))]);
+        assert_eq!(bursts(&times[2..3]), vec![]);
+    }
+
+    #[test]
+    fn test_heatmap_text() {
+        let stats = CallStats::build(
+            &[
+                call("0612345678", at(12, 0, 0), false),
+                call("0612345678", at(12, 0, 30), false),
+                call("3331234567", at(18, 23, 0), false),
+            ],
+            at(12, 0, 0),
+            at(19, 0, 0),
+        );
+        let text = stats.heatmap_text();
+        let lines: Vec<&str> = text.lines().collect();
+
+        assert_eq!(lines.len(), 8);
+        assert!(lines[1].starts_with("Mon █·"));
+        assert!(lines[7].starts_with("Sun ·"));
+        assert!(lines[7].ends_with('▒'));
+    }
+
+    #[test]
+    fn test_no_calls() {
+        let stats = CallStats::build(&[], at(12, 0, 0), at(19, 0, 0));
+
+        assert_eq!(stats.missed_ratio(), None);
+        assert!(stats.message(&Contacts::default()).ends_with("No calls."));
+    }
+}
@@ -0,0 +1,111 @@
+use crate::store;
+use crate::timm::caller::CallerId;
+use serde::{Deserialize, Serialize};
+use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
+
+const FILE: &str = "contacts.json";
+
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug)]
+pub struct Contacts {
+    names: BTreeMap<String, String>,
+    #[serde(default)]
+    vips: BTreeSet<String>,
+}
+
+impl Contacts {
+    pub fn load() -> Self {
+        let mut contacts: Contacts = store::load(FILE);
+
+        if contacts.migrate() {
+            info!("Moving the contacts to E.164 numbers");
+            contacts.save();
+        }
+
+        contacts
+    }
+
+    pub fn save(&self) -> Option<()> {
+        store::save(FILE, self)
+    }
+
+    // earlier versions kept numbers as typed, so two of them may now be the same number
+    fn migrate(&mut self) -> bool {
+        let mut names: BTreeMap<String, String> = BTreeMap::new();
+
+        for (number, name) in &self.names {
+            match names.entry(key(number)) {
+                Entry::Vacant(entry) => {
+                    entry.insert(name.clone());
+                }
+                Entry::Occupied(entry) if entry.get() != name => warn!(
+                    "{} is the same number as {}, keeping the name {} rather than {}",
+                    number,
+                    entry.key(),
+                    entry.get(),
+                    name
+                ),
+                Entry::Occupied(_) => {}
+            }
+        }
+
+        let vips: BTreeSet<String> = self.vips.iter().map(|number| key(number)).collect();
+        let changed = names != self.names || vips != self.vips;
+
+        self.names = names;
+        self.vips = vips;
+
+        changed
+    }
+
+    pub fn name(&self, number: &str) -> Option<&str> {
+        self.names.get(&key(number)).map(String::as_str)
+    }
+
+    pub fn insert(&mut self, number: &str, name: &str) {
+        self.names.insert(key(number), name.trim().to_string());
+    }
+
+    /// VIP calls ring through quiet hours and do-not-disturb.
+    pub fn is_vip(&self, number: &str) -> bool {
+        self.vips.contains(&key(number))
+    }
+
+    pub fn toggle_vip(&mut self, number: &str) -> bool {
+        let number = key(number);
+
+        if self.vips.remove(&number) {
+            false
+        } else {
+            self.vips.insert(number)
+        }
+    }
+}
+
+// "06 1234 5678" and "+39 0612345678" are the same number
+fn key(number: &str) -> String {
+    CallerId::from(number).key()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migrate() {
+        let mut contacts = Contacts {
+            names: BTreeMap::from([
+                ("+390612345678".to_string(), "Nonna".to_string()),
+                ("06 1234 5678".to_string(), "Grandma".to_string()),
+                ("3331234567".to_string(), "Luca".to_string()),
+            ]),
+            vips: BTreeSet::from(["333 123 4567".to_string()]),
+        };
+
+        assert!(contacts.migrate());
+        assert_eq!(contacts.name("0612345678"), Some("Nonna"));
+        assert_eq!(contacts.name("+39 333 1234567"), Some("Luca"));
+        assert!(contacts.is_vip("3331234567"));
+        assert!(!contacts.migrate());
+    }
+}
@@ -0,0 +1,357 @@
+use crate::contacts::Contacts;
+use crate::history::Record;
+use crate::schedule;
+use crate::timm::caller::CallerId;
+use crate::timm::stats::{LineSpeed, LineStats};
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use std::env;
+
+// monitor_speed samples every 5 minutes, longer gaps mean the bot wasn't running
+const MAX_SAMPLE_GAP: i64 = 15;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Period {
+    Daily,
+    Weekly,
+}
+
+impl Period {
+    pub fn length(&self) -> Duration {
+        match self {
+            Period::Daily => Duration::days(1),
+            Period::Weekly => Duration::weeks(1),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Default)]
+pub struct DigestSchedule {
+    daily: Option<NaiveTime>,
+    weekly: Option<(Weekday, NaiveTime)>,
+}
+
+impl DigestSchedule {
+    /// Reads `DIGEST_DAILY`, such as "21:00", and `DIGEST_WEEKLY`, such as "Mon 09:00".
+    pub fn from_env() -> Self {
+        DigestSchedule::parse(
+            &env::var("DIGEST_DAILY").unwrap_or_default(),
+            &env::var("DIGEST_WEEKLY").unwrap_or_default(),
+        )
+    }
+
+    pub fn parse(daily: &str, weekly: &str) -> Self {
+        let weekly = weekly
+            .trim()
+            .split_once(' ')
+            .and_then(|(day, time)| Some((day.parse().ok()?, schedule::parse_time(time)?)));
+
+        DigestSchedule {
+            daily: schedule::parse_time(daily),
+            weekly,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.daily.is_none() && self.weekly.is_none()
+    }
+
+    /// The digests whose time came after `after` and no later than `until`.
+    pub fn due(&self, after: NaiveDateTime, until: NaiveDateTime) -> Vec<Period> {
+        let mut due = Vec::new();
+        let is_due = |at: NaiveDateTime| after < at && at <= until;
+
+        for date in after
+            .date()
+            .iter_days()
+            .take_while(|date| *date <= until.date())
+        {
+            if let Some(time) = self.daily {
+                if is_due(date.and_time(time)) {
+                    due.push(Period::Daily);
+                }
+            }
+
+            if let Some((weekday, time)) = self.weekly {
+                if date.weekday() == weekday && is_due(date.and_time(time)) {
+                    due.push(Period::Weekly);
+                }
+            }
+        }
+
+        due.dedup();
+        due
+    }
+}
+
+/// A summary of the calls and the line over a day or a week.
+#[derive(PartialEq, Debug)]
+pub struct Digest {
+    pub period: Period,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+    pub calls: usize,
+    pub missed: usize,
+    pub callers: Vec<(CallerId, usize)>,
+    pub new_callers: Vec<CallerId>,
+    pub uptime: Option<f64>,
+    pub download: Option<u32>,
+    pub upload: Option<u32>,
+    pub slow: Duration,
+    pub bad: Duration,
+    pub ip_changes: usize,
+    pub reboots: usize,
+}
+
+impl Digest {
+    /// Builds the digest for the period ending at `to` out of the whole history.
+    pub fn build(period: Period, records: &[Record], to: NaiveDateTime) -> Self {
+        let from = to - period.length();
+        let mut records: Vec<&Record> = records.iter().filter(|r| r.at() < to).collect();
+        records.sort_by_key(|record| record.at());
+
+        let mut callers: BTreeMap<String, (CallerId, usize)> = BTreeMap::new();
+        let mut known: Vec<String> = Vec::new();
+        let mut new_callers = Vec::new();
+        let (mut calls, mut missed, mut reboots, mut unreachable) = (0, 0, 0, 0);
+        let mut samples = Vec::new();
+
+        for record in &records {
+            match record {
+                Record::Call(phone_call) if phone_call.when < from => {
+                    known.push(phone_call.who.key());
+                }
+                Record::Call(phone_call) => {
+                    let key = phone_call.who.key();
+
+                    calls += 1;
+                    if phone_call.missed {
+                        missed += 1;
+                    }
+                    if phone_call.who.number().is_some() && !known.contains(&key) {
+                        known.push(key.clone());
+                        new_callers.push(phone_call.who.clone());
+                    }
+
+                    callers
+                        .entry(key)
+                        .or_insert_with(|| (phone_call.who.clone(), 0))
+                        .1 += 1;
+                }
+                Record::Line { at, stats } if *at >= from => samples.push((*at, stats)),
+                Record::Unreachable { at } if *at >= from => unreachable += 1,
+                Record::Reboot { at } if *at >= from => reboots += 1,
+                _ => {}
+            }
+        }
+
+        let mut callers: Vec<(CallerId, usize)> = callers.into_values().collect();
+        callers.sort_by_key(|(_, count)| Reverse(*count));
+
+        let checks = samples.len() + unreachable;
+        let uptime = (checks > 0).then(|| samples.len() as f64 * 100.0 / checks as f64);
+        let average = |value: fn(&LineStats) -> u32| {
+            (!samples.is_empty()).then(|| {
+                (samples
+                    .iter()
+                    .map(|(_, stats)| value(stats) as u64)
+                    .sum::<u64>()
+                    / samples.len() as u64) as u32
+            })
+        };
+        let download = average(|stats| stats.download);
+        let upload = average(|stats| stats.upload);
+
+        let (mut slow, mut bad) = (Duration::zero(), Duration::zero());
+        for (index, (at, stats)) in samples.iter().enumerate() {
+            let next = samples.get(index + 1).map_or(to, |(next, _)| *next);
+            let lasted = (next - *at).min(Duration::minutes(MAX_SAMPLE_GAP));
+
+            match stats.speed {
+                LineSpeed::Slow => slow += lasted,
+                LineSpeed::Bad => bad += lasted,
+                LineSpeed::Normal => {}
+            }
+        }
+
+        let ip_changes = samples
+            .windows(2)
+            .filter(|pair| pair[0].1.ip != pair[1].1.ip)
+            .count();
+
+        Digest {
+            period,
+            from,
+            to,
+            calls,
+            missed,
+            callers,
+            new_callers,
+            uptime,
+            download,
+            upload,
+            slow,
+            bad,
+            ip_changes,
+            reboots,
+        }
+    }
+
+    pub fn message(&self, contacts: &Contacts) -> String {
+        let name = |caller: &CallerId| match contacts.name(&caller.key()) {
+            Some(name) => format!("👤 {}", name),
+            None => caller.to_string(),
+        };
+
+        let mut lines = vec![format!(
+            "📊 {} digest, {} → {}",
+            match self.period {
+                Period::Daily => "Daily",
+                Period::Weekly => "Weekly",
+            },
+            self.from.format("%-d %b %H:%M"),
+            self.to.format("%-d %b %H:%M")
+        )];
+
+        if self.calls == 0 {
+            lines.push("No calls.".to_string());
+        } else {
+            lines.push(format!("{} calls, {} missed:", self.calls, self.missed));
+            for (caller, count) in &self.callers {
+                lines.push(format!("{} ×{}", name(caller), count));
+            }
+        }
+
+        if !self.new_callers.is_empty() {
+            let new_callers: Vec<String> = self.new_callers.iter().map(name).collect();
+            lines.push(format!("🆕 New numbers: {}", new_callers.join(", ")));
+        }
+
+        if let (Some(uptime), Some(download), Some(upload)) =
+            (self.uptime, self.download, self.upload)
+        {
+            lines.push(format!(
+                "📶 Modem reachable {:.1}% of the time, 🔻 {}kbps 🔺 {}kbps on average",
+                uptime, download, upload
+            ));
+        } else {
+            lines.push("📶 No line samples.".to_string());
+        }
+
+        if self.slow > Duration::zero() || self.bad > Duration::zero() {
+            lines.push(format!(
+                "⚠️ Slow for {}, bad for {}",
+                schedule::format_duration(self.slow),
+                schedule::format_duration(self.bad)
+            ));
+        }
+
+        lines.push(format!(
+            "🌐 {} IP changes, 🔄 {} reboots",
+            self.ip_changes, self.reboots
+        ));
+
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timm::calls::PhoneCall;
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    // 5 October 2026 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn call(who: &str, when: NaiveDateTime, missed: bool) -> Record {
+        Record::Call(PhoneCall {
+            who: CallerId::from(who),
+            when,
+            missed,
+        })
+    }
+
+    fn line(at: NaiveDateTime, ip: &str, speed: LineSpeed) -> Record {
+        Record::Line {
+            at,
+            stats: LineStats {
+                ip: ip.to_string(),
+                download: 10000,
+                upload: 2000,
+                speed,
+            },
+        }
+    }
+
+    #[test]
+    fn test_schedule_due() {
+        let schedule = DigestSchedule::parse("21:00", "Mon 09:00");
+
+        assert_eq!(
+            schedule.due(at(5, 8, 59), at(5, 9, 0)),
+            vec![Period::Weekly]
+        );
+        assert_eq!(schedule.due(at(5, 9, 0), at(5, 9, 1)), vec![]);
+        assert_eq!(
+            schedule.due(at(6, 20, 59), at(6, 21, 0)),
+            vec![Period::Daily]
+        );
+        assert_eq!(schedule.due(at(6, 9, 0), at(6, 9, 1)), vec![]);
+        assert!(DigestSchedule::parse("", "").is_empty());
+    }
+
+    #[test]
+    fn test_digest_calls() {
+        let records = vec![
+            call("0612345678", at(4, 10, 0), false),
+            call("0612345678", at(5, 10, 0), false),
+            call("3331234567", at(5, 11, 0), true),
+            call("3331234567", at(5, 12, 0), false),
+            call("Anonimo", at(5, 13, 0), true),
+        ];
+        let digest = Digest::build(Period::Daily, &records, at(5, 21, 0));
+
+        assert_eq!(digest.calls, 4);
+        assert_eq!(digest.missed, 2);
+        assert_eq!(digest.callers[0], (CallerId::from("3331234567"), 2));
+        assert_eq!(digest.new_callers, vec![CallerId::from("3331234567")]);
+    }
+
+    #[test]
+    fn test_digest_line() {
+        let records = vec![
+            line(at(5, 12, 0), "1.2.3.4", LineSpeed::Normal),
+            line(at(5, 12, 5), "1.2.3.4", LineSpeed::Slow),
+            line(at(5, 12, 10), "5.6.7.8", LineSpeed::Bad),
+            Record::Unreachable { at: at(5, 12, 15) },
+            Record::Reboot { at: at(5, 12, 16) },
+        ];
+        let digest = Digest::build(Period::Daily, &records, at(5, 21, 0));
+
+        assert_eq!(digest.uptime, Some(75.0));
+        assert_eq!(digest.download, Some(10000));
+        assert_eq!(digest.slow, Duration::minutes(5));
+        // the last sample only counts up to the longest gap between samples
+        assert_eq!(digest.bad, Duration::minutes(MAX_SAMPLE_GAP));
+        assert_eq!(digest.ip_changes, 1);
+        assert_eq!(digest.reboots, 1);
+    }
+
+    #[test]
+    fn test_empty_digest_message() {
+        let digest = Digest::build(Period::Weekly, &[], at(5, 9, 0));
+
+        assert_eq!(
+            digest.message(&Contacts::default()),
+            "📊 Weekly digest, 28 Sep 09:00 → 5 Oct 09:00\nNo calls.\n📶 No line samples.\n🌐 0 IP changes, 🔄 0 reboots"
+        );
+    }
+}
@@ -0,0 +1,172 @@
+use crate::timm::calls::PhoneCall;
+use chrono::Duration;
+use std::env;
+use teloxide::types::ChatId;
+
+// calls a day apart aren't urgent, and longer windows would overflow chrono
+const MAX_MINUTES: i64 = 24 * 60;
+
+/// Calls from one number coming close together, which usually means it's urgent: they're
+/// announced loudly, even in quiet hours, and to the extra chats as well.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Escalation {
+    /// How many calls within how many minutes, or None to never escalate.
+    pub repeat: Option<(usize, i64)>,
+    pub chats: Vec<ChatId>,
+}
+
+impl Default for Escalation {
+    fn default() -> Self {
+        Escalation {
+            repeat: Some((3, 10)),
+            chats: Vec::new(),
+        }
+    }
+}
+
+impl Escalation {
+    /// Reads `ESCALATE_REPEAT`, such as "3/10" for 3 calls in 10 minutes or "off", and
+    /// `ESCALATE_CHATS`, the ids of other chats to alert too.
+    pub fn from_env() -> Self {
+        let mut escalation = Escalation::default();
+
+        if let Ok(repeat) = env::var("ESCALATE_REPEAT") {
+            escalation.repeat = parse_repeat(&repeat);
+        }
+
+        if let Ok(chats) = env::var("ESCALATE_CHATS") {
+            escalation.chats = parse_chats(&chats);
+        }
+
+        escalation
+    }
+
+    /// How many times the caller of `phone_call` rang within the window up to it, when that's
+    /// enough to escalate, `phone_calls` being every call the modem remembers.
+    pub fn check(&self, phone_call: &PhoneCall, phone_calls: &[PhoneCall]) -> Option<usize> {
+        let (times, minutes) = self.repeat?;
+
+        // withheld numbers could be anybody
+        phone_call.who.number()?;
+
+        let since = phone_call.when - Duration::minutes(minutes);
+        let count = phone_calls
+            .iter()
+            .filter(|other| other.who == phone_call.who)
+            .filter(|other| other.when > since && other.when <= phone_call.when)
+            .count();
+
+        (count >= times).then_some(count)
+    }
+
+    pub fn message(&self, text: &str, count: usize) -> String {
+        format!(
+            "🚨 {}\nCalled {} times in {} minutes, it may be urgent!",
+            text,
+            count,
+            self.repeat.map_or(0, |(_, minutes)| minutes)
+        )
+    }
+}
+
+/// Reads "3/10" as three calls in ten minutes, or "off", within a day at most.
+fn parse_repeat(value: &str) -> Option<(usize, i64)> {
+    let (times, minutes) = value.trim().split_once('/')?;
+
+    match (times.trim().parse(), minutes.trim().parse()) {
+        (Ok(times), Ok(minutes)) if times > 1 && minutes > 0 && minutes <= MAX_MINUTES => {
+            Some((times, minutes))
+        }
+        _ => None,
+    }
+}
+
+fn parse_chats(value: &str) -> Vec<ChatId> {
+    value
+        .split(',')
+        .filter_map(|chat| match chat.trim().parse() {
+            Ok(chat) => Some(ChatId(chat)),
+            Err(_) => {
+                if !chat.trim().is_empty() {
+                    warn!("Couldn't parse escalation chat {}", chat);
+                }
+                None
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timm::caller::CallerId;
+    use chrono::{NaiveDate, NaiveDateTime};
+
+    use super::*;
+
+    fn at(minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 5)
+            .unwrap()
+            .and_hms_opt(9, minute, 0)
+            .unwrap()
+    }
+
+    fn call(who: &str, minute: u32) -> PhoneCall {
+        PhoneCall {
+            who: CallerId::from(who),
+            when: at(minute),
+            missed: true,
+        }
+    }
+
+    #[test]
+    fn test_check() {
+        let escalation = Escalation::default();
+        let phone_calls = vec![
+            call("3331234567", 0),
+            call("3331234567", 4),
+            call("0612345678", 5),
+            call("3331234567", 9),
+            call("3331234567", 30),
+        ];
+
+        assert_eq!(escalation.check(&phone_calls[1], &phone_calls), None);
+        assert_eq!(escalation.check(&phone_calls[3], &phone_calls), Some(3));
+        assert_eq!(escalation.check(&phone_calls[4], &phone_calls), None);
+        assert_eq!(
+            Escalation {
+                repeat: None,
+                ..Escalation::default()
+            }
+            .check(&phone_calls[3], &phone_calls),
+            None
+        );
+    }
+
+    #[test]
+    fn test_withheld_calls_are_not_escalated() {
+        let phone_calls: Vec<PhoneCall> = (0..3)
+            .map(|minute| PhoneCall {
+                who: CallerId::Anonymous,
+                ..call("3331234567", minute)
+            })
+            .collect();
+
+        assert_eq!(
+            Escalation::default().check(&phone_calls[2], &phone_calls),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse_repeat("5/30"), Some((5, 30)));
+        assert_eq!(parse_repeat("off"), None);
+        assert_eq!(parse_repeat("1/10"), None);
+        assert_eq!(parse_repeat("3/1440"), Some((3, 1440)));
+        assert_eq!(parse_repeat("3/999999999"), None);
+        assert_eq!(
+            parse_chats("123, -100456,"),
+            vec![ChatId(123), ChatId(-100456)]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+use crate::outage::Outage;
+use crate::probe::ProbeResult;
+use crate::timm::calls::PhoneCall;
+use crate::timm::devices::Device;
+use crate::timm::stats::LineStats;
+use crate::timm::wifi::Network;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Something a monitor noticed, published on the bus for the alerts and the rules to act on.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Event {
+    NewCall(PhoneCall),
+    /// Every reading of the line, changed or not.
+    LineSample(LineStats),
+    /// The line got slower or recovered, with what the modem's event log says caused it.
+    SpeedStateChanged {
+        stats: LineStats,
+        reason: Option<String>,
+    },
+    IpChanged {
+        stats: LineStats,
+        reason: Option<String>,
+    },
+    ModemUnreachable {
+        at: NaiveDateTime,
+    },
+    /// The modem was told to reboot, by a command or a rule.
+    RebootIssued {
+        at: NaiveDateTime,
+        by: String,
+    },
+    /// Published again on every check until the chat has heard about it.
+    OutageStarted(Outage),
+    OutageEnded(Outage),
+    /// A probe target has been poor since `since`, for at least as long as the rule says.
+    ConnectionDegraded {
+        result: ProbeResult,
+        since: NaiveDateTime,
+    },
+    ConnectionRecovered(ProbeResult),
+    NewDevice(Device),
+    VoipRegistration {
+        line: String,
+        registered: bool,
+    },
+    /// A Wi-Fi network switched back once its timer ran out.
+    WifiSwitched {
+        network: Network,
+        enabled: bool,
+    },
+    /// A port forward closed once it expired.
+    PortClosed {
+        external: u16,
+    },
+    /// A modem page the parsers can't read any more, with a copy of it when one could be saved.
+    LayoutChanged {
+        page: String,
+        mismatch: String,
+        copy: Option<PathBuf>,
+    },
+}
+
+/// The kinds of event, as rules name them.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EventKind {
+    NewCall,
+    LineSample,
+    #[serde(alias = "speed_changed")]
+    SpeedStateChanged,
+    IpChanged,
+    ModemUnreachable,
+    RebootIssued,
+    OutageStarted,
+    OutageEnded,
+    ConnectionDegraded,
+    ConnectionRecovered,
+    NewDevice,
+    VoipRegistration,
+    WifiSwitched,
+    PortClosed,
+    LayoutChanged,
+}
+
+impl Event {
+    pub fn kind(&self) -> EventKind {
+        match self {
+            Event::NewCall(_) => EventKind::NewCall,
+            Event::LineSample(_) => EventKind::LineSample,
+            Event::SpeedStateChanged { .. } => EventKind::SpeedStateChanged,
+            Event::IpChanged { .. } => EventKind::IpChanged,
+            Event::ModemUnreachable { .. } => EventKind::ModemUnreachable,
+            Event::RebootIssued { .. } => EventKind::RebootIssued,
+            Event::OutageStarted(_) => EventKind::OutageStarted,
+            Event::OutageEnded(_) => EventKind::OutageEnded,
+            Event::ConnectionDegraded { .. } => EventKind::ConnectionDegraded,
+            Event::ConnectionRecovered(_) => EventKind::ConnectionRecovered,
+            Event::NewDevice(_) => EventKind::NewDevice,
+            Event::VoipRegistration { .. } => EventKind::VoipRegistration,
+            Event::WifiSwitched { .. } => EventKind::WifiSwitched,
+            Event::PortClosed { .. } => EventKind::PortClosed,
+            Event::LayoutChanged { .. } => EventKind::LayoutChanged,
+        }
+    }
+
+    pub fn phone_call(&self) -> Option<&PhoneCall> {
+        match self {
+            Event::NewCall(phone_call) => Some(phone_call),
+            _ => None,
+        }
+    }
+
+    pub fn stats(&self) -> Option<&LineStats> {
+        match self {
+            Event::LineSample(stats)
+            | Event::SpeedStateChanged { stats, .. }
+            | Event::IpChanged { stats, .. } => Some(stats),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,255 @@
+use crate::contacts::Contacts;
+use crate::schedule;
+use crate::timm::calls::PhoneCall;
+use chrono::{Duration, NaiveDateTime, Utc};
+use serde::Serialize;
+
+pub const CSV_HEADER: &str = "when,number,name,missed";
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Format {
+    Csv,
+    Json,
+    Ics,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Csv => "csv",
+            Format::Json => "json",
+            Format::Ics => "ics",
+        }
+    }
+}
+
+impl TryFrom<&str> for Format {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_lowercase().as_str() {
+            "csv" => Ok(Format::Csv),
+            "json" => Ok(Format::Json),
+            "ics" | "ical" | "icalendar" => Ok(Format::Ics),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A call as exported, with the contact's name when there is one.
+#[derive(PartialEq, Eq, Debug, Serialize)]
+pub struct Row {
+    pub when: NaiveDateTime,
+    pub number: String,
+    pub name: Option<String>,
+    pub missed: bool,
+}
+
+impl Row {
+    pub fn new(phone_call: &PhoneCall, contacts: &Contacts) -> Self {
+        let number = phone_call.who.key();
+
+        Row {
+            when: phone_call.when,
+            name: contacts.name(&number).map(String::from),
+            number,
+            missed: phone_call.missed,
+        }
+    }
+}
+
+/// Reads "[period] [format]" in either order, such as "30d csv" or "ics"; without a
+/// period the whole history is exported, and without a format it's CSV.
+pub fn parse_args(value: &str) -> Option<(Option<Duration>, Format)> {
+    let mut period = None;
+    let mut format = None;
+
+    for word in value.split_whitespace() {
+        // each may be given once only
+        let repeated = if let Ok(parsed) = Format::try_from(word) {
+            format.replace(parsed).is_some()
+        } else if word.eq_ignore_ascii_case("all") {
+            false
+        } else {
+            period.replace(schedule::parse_duration(word)?).is_some()
+        };
+
+        if repeated {
+            return None;
+        }
+    }
+
+    Some((period, format.unwrap_or(Format::Csv)))
+}
+
+pub fn export(format: Format, phone_calls: &[PhoneCall], contacts: &Contacts) -> String {
+    match format {
+        Format::Csv => csv(phone_calls, contacts),
+        Format::Json => json(phone_calls, contacts),
+        Format::Ics => ics(phone_calls, contacts),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn csv_row(phone_call: &PhoneCall, contacts: &Contacts) -> String {
+    let row = Row::new(phone_call, contacts);
+
+    format!(
+        "{},{},{},{}",
+        row.when.format("%Y-%m-%d %H:%M:%S"),
+        csv_field(&row.number),
+        csv_field(row.name.as_deref().unwrap_or_default()),
+        row.missed
+    )
+}
+
+pub fn csv(phone_calls: &[PhoneCall], contacts: &Contacts) -> String {
+    let mut lines = vec![CSV_HEADER.to_string()];
+    lines.extend(
+        phone_calls
+            .iter()
+            .map(|phone_call| csv_row(phone_call, contacts)),
+    );
+
+    lines.join("\n") + "\n"
+}
+
+pub fn json(phone_calls: &[PhoneCall], contacts: &Contacts) -> String {
+    let rows: Vec<Row> = phone_calls
+        .iter()
+        .map(|phone_call| Row::new(phone_call, contacts))
+        .collect();
+
+    serde_json::to_string_pretty(&rows).unwrap_or_default() + "\n"
+}
+
+fn ics_text(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// An iCalendar with an event at the time of each call; the times are the modem's, so
+/// they're left floating rather than given a zone.
+pub fn ics(phone_calls: &[PhoneCall], contacts: &Contacts) -> String {
+    let stamp = Utc::now().format("%Y%m%dT%H%M%SZ");
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        "PRODID:-//callog_bot//calls//EN".to_string(),
+    ];
+
+    for phone_call in phone_calls {
+        let row = Row::new(phone_call, contacts);
+        let caller = match &row.name {
+            Some(name) => format!("{} ({})", name, row.number),
+            None => row.number.clone(),
+        };
+        let summary = if row.missed {
+            format!("Missed call from {}", caller)
+        } else {
+            format!("Call from {}", caller)
+        };
+        let start = row.when.format("%Y%m%dT%H%M%S");
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            // the same across exports, so importing twice doesn't duplicate the calls
+            format!("UID:{}-{}@callog_bot", start, ics_text(&row.number)),
+            format!("DTSTAMP:{}", stamp),
+            format!("DTSTART:{}", start),
+            "DURATION:PT1M".to_string(),
+            format!("SUMMARY:{}", ics_text(&summary)),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+
+    lines.push("END:VCALENDAR".to_string());
+
+    lines.join("\r\n") + "\r\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timm::caller::CallerId;
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn calls() -> Vec<PhoneCall> {
+        let when = NaiveDate::from_ymd_opt(2026, 10, 4)
+            .unwrap()
+            .and_hms_opt(9, 30, 0)
+            .unwrap();
+
+        vec![
+            PhoneCall {
+                who: CallerId::from("0612345678"),
+                when,
+                missed: false,
+            },
+            PhoneCall {
+                who: CallerId::Anonymous,
+                when: when + Duration::hours(2),
+                missed: true,
+            },
+        ]
+    }
+
+    fn contacts() -> Contacts {
+        let mut contacts = Contacts::default();
+        contacts.insert("0612345678", "Rossi, Mario");
+        contacts
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(parse_args(""), Some((None, Format::Csv)));
+        assert_eq!(
+            parse_args("ics 30d"),
+            Some((Some(Duration::days(30)), Format::Ics))
+        );
+        assert_eq!(parse_args("all json"), Some((None, Format::Json)));
+        assert_eq!(parse_args("csv json"), None);
+        assert_eq!(parse_args("lately"), None);
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(
+            csv(&calls(), &contacts()),
+            "when,number,name,missed\n2026-10-04 09:30:00,+390612345678,\"Rossi, Mario\",false\n2026-10-04 11:30:00,anonymous,,true\n"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json: serde_json::Value = serde_json::from_str(&json(&calls(), &contacts())).unwrap();
+
+        assert_eq!(json[0]["when"], "2026-10-04T09:30:00");
+        assert_eq!(json[0]["name"], "Rossi, Mario");
+        assert_eq!(json[1]["name"], serde_json::Value::Null);
+        assert_eq!(json[1]["missed"], true);
+    }
+
+    #[test]
+    fn test_ics() {
+        let ics = ics(&calls(), &contacts());
+
+        assert!(ics.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(ics.ends_with("END:VCALENDAR\r\n"));
+        assert_eq!(ics.matches("BEGIN:VEVENT").count(), 2);
+        assert!(ics.contains("DTSTART:20261004T093000\r\n"));
+        assert!(ics.contains("SUMMARY:Call from Rossi\\, Mario (+390612345678)\r\n"));
+        assert!(ics.contains("SUMMARY:Missed call from anonymous\r\n"));
+    }
+}
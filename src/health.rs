@@ -0,0 +1,230 @@
+use crate::schedule::{self, format_duration};
+use chrono::{Duration, NaiveDateTime};
+use std::collections::BTreeMap;
+use std::env;
+use std::sync::{Arc, Mutex};
+
+/// How one monitor has been doing.
+#[derive(PartialEq, Eq, Debug, Clone, Default)]
+pub struct MonitorHealth {
+    pub last_success: Option<NaiveDateTime>,
+    pub last_failure: Option<NaiveDateTime>,
+    /// Failures since the last success.
+    pub failures: u32,
+    pub last_error: Option<String>,
+    // whether the chat was told it stopped working
+    alerted: bool,
+}
+
+/// How every monitor has been doing since the bot started, shared by the monitors, `/status`
+/// and the heartbeat.
+#[derive(Clone, Debug)]
+pub struct Health {
+    pub started: NaiveDateTime,
+    monitors: Arc<Mutex<BTreeMap<String, MonitorHealth>>>,
+}
+
+impl Health {
+    pub fn new(started: NaiveDateTime) -> Self {
+        Health {
+            started,
+            monitors: Arc::new(Mutex::new(BTreeMap::new())),
+        }
+    }
+
+    /// Starts following a monitor, so one that never gets to finish a check is noticed too.
+    pub fn watch(&self, monitor: &str) {
+        self.monitors
+            .lock()
+            .unwrap()
+            .entry(monitor.to_string())
+            .or_default();
+    }
+
+    /// Notes how a check of `monitor` went.
+    pub fn record(&self, monitor: &str, at: NaiveDateTime, result: Result<(), String>) {
+        let mut monitors = self.monitors.lock().unwrap();
+        let health = monitors.entry(monitor.to_string()).or_default();
+
+        match result {
+            Ok(()) => {
+                health.last_success = Some(at);
+                health.failures = 0;
+            }
+            Err(error) => {
+                warn!("{} failed: {}", monitor, error);
+                health.last_failure = Some(at);
+                health.failures += 1;
+                health.last_error = Some(error);
+            }
+        }
+    }
+
+    pub fn monitors(&self) -> BTreeMap<String, MonitorHealth> {
+        self.monitors.lock().unwrap().clone()
+    }
+
+    /// The alerts for monitors that haven't worked for `window`, once each, and for those that
+    /// work again after one.
+    pub fn heartbeat(&self, now: NaiveDateTime, window: Duration) -> Vec<String> {
+        let mut monitors = self.monitors.lock().unwrap();
+        let mut alerts = Vec::new();
+
+        for (name, health) in monitors.iter_mut() {
+            let since = health.last_success.unwrap_or(self.started);
+            let overdue = now - since >= window;
+
+            if overdue && !health.alerted {
+                let error = match &health.last_error {
+                    Some(error) if health.failures > 0 => format!(": {}", error),
+                    _ => String::new(),
+                };
+                alerts.push(format!(
+                    "🩺 {} hasn't worked for {}{}.",
+                    name,
+                    format_duration(now - since),
+                    error
+                ));
+            } else if !overdue && health.alerted {
+                alerts.push(format!("🩺 {} works again.", name));
+            }
+
+            health.alerted = overdue;
+        }
+
+        alerts
+    }
+
+    /// The text of `/status`, `storage` being the size of the data directory.
+    pub fn status(&self, now: NaiveDateTime, storage: u64) -> String {
+        let mut lines = vec![
+            format!(
+                "🩺 callog_bot {}, up {}",
+                env!("CARGO_PKG_VERSION"),
+                format_duration(now - self.started)
+            ),
+            format!("💾 {} stored", format_size(storage)),
+            String::new(),
+        ];
+
+        for (name, health) in self.monitors() {
+            let worked = match health.last_success {
+                Some(at) => format!("worked {} ago", format_duration(now - at)),
+                None => "hasn't worked yet".to_string(),
+            };
+
+            if health.failures == 0 {
+                lines.push(format!("✅ {}: {}", name, worked));
+            } else {
+                lines.push(format!(
+                    "⚠️ {}: failing ({} in a row), {}",
+                    name, health.failures, worked
+                ));
+            }
+
+            if let (Some(error), Some(at)) = (&health.last_error, health.last_failure) {
+                lines.push(format!(
+                    "    last error {} ago: {}",
+                    format_duration(now - at),
+                    error
+                ));
+            }
+        }
+
+        lines.join("\n")
+    }
+}
+
+/// How long a monitor can go without working before the chat is told, `HEALTH_WINDOW` such as
+/// "30m", or 30 minutes by default.
+pub fn window_from_env() -> Duration {
+    env::var("HEALTH_WINDOW")
+        .ok()
+        .and_then(|window| schedule::parse_duration(&window))
+        .unwrap_or_else(|| Duration::minutes(30))
+}
+
+fn format_size(bytes: u64) -> String {
+    match bytes {
+        bytes if bytes >= 1024 * 1024 => format!("{:.1} MB", bytes as f64 / 1024.0 / 1024.0),
+        bytes if bytes >= 1024 => format!("{:.1} kB", bytes as f64 / 1024.0),
+        bytes => format!("{} B", bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 5)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_record() {
+        let health = Health::new(at(9, 0));
+        health.record("monitor_calls", at(9, 1), Ok(()));
+        health.record("monitor_calls", at(9, 2), Err("no modem".to_string()));
+        health.record("monitor_calls", at(9, 3), Err("still no modem".to_string()));
+
+        let monitors = health.monitors();
+        let calls = &monitors["monitor_calls"];
+        assert_eq!(calls.last_success, Some(at(9, 1)));
+        assert_eq!(calls.last_failure, Some(at(9, 3)));
+        assert_eq!(calls.failures, 2);
+        assert_eq!(calls.last_error.as_deref(), Some("still no modem"));
+
+        health.record("monitor_calls", at(9, 4), Ok(()));
+        assert_eq!(health.monitors()["monitor_calls"].failures, 0);
+    }
+
+    #[test]
+    fn test_heartbeat() {
+        let health = Health::new(at(9, 0));
+        let window = Duration::minutes(30);
+        health.watch("monitor_speed");
+        health.record("monitor_calls", at(9, 10), Ok(()));
+        health.record("monitor_calls", at(9, 20), Err("no modem".to_string()));
+
+        assert!(health.heartbeat(at(9, 29), window).is_empty());
+        assert_eq!(
+            health.heartbeat(at(9, 31), window),
+            vec!["🩺 monitor_speed hasn't worked for 31m.".to_string()]
+        );
+        assert_eq!(
+            health.heartbeat(at(9, 40), window),
+            vec!["🩺 monitor_calls hasn't worked for 30m: no modem.".to_string()]
+        );
+        assert!(health.heartbeat(at(9, 50), window).is_empty());
+
+        health.record("monitor_calls", at(9, 55), Ok(()));
+        assert_eq!(
+            health.heartbeat(at(9, 56), window),
+            vec!["🩺 monitor_calls works again.".to_string()]
+        );
+    }
+
+    #[test]
+    fn test_status() {
+        let health = Health::new(at(7, 0));
+        health.record("monitor_calls", at(9, 0), Ok(()));
+        health.record("monitor_speed", at(8, 0), Ok(()));
+        health.record("monitor_speed", at(8, 55), Err("no modem".to_string()));
+
+        assert_eq!(
+            health.status(at(9, 5), 1536),
+            format!(
+                "🩺 callog_bot {}, up 2h 5m\n💾 1.5 kB stored\n\n\
+                 ✅ monitor_calls: worked 5m ago\n\
+                 ⚠️ monitor_speed: failing (1 in a row), worked 1h 5m ago\n    \
+                 last error 10m ago: no modem",
+                env!("CARGO_PKG_VERSION")
+            )
+        );
+    }
+}
@@ -0,0 +1,185 @@
+use crate::outage::Outage;
+use crate::probe::ProbeResult;
+use crate::speedtest::SpeedTest;
+use crate::store;
+use crate::timm::calls::PhoneCall;
+use crate::timm::stats::LineStats;
+use chrono::{Duration, Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::env;
+
+const FILE: &str = "history.jsonl";
+
+/// Something the monitors saw, kept for digests and statistics.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub enum Record {
+    Call(PhoneCall),
+    Line {
+        at: NaiveDateTime,
+        stats: LineStats,
+    },
+    Unreachable {
+        at: NaiveDateTime,
+    },
+    Reboot {
+        at: NaiveDateTime,
+    },
+    Outage(Outage),
+    Probe {
+        at: NaiveDateTime,
+        results: Vec<ProbeResult>,
+    },
+    SpeedTest(SpeedTest),
+}
+
+impl Record {
+    pub fn at(&self) -> NaiveDateTime {
+        match self {
+            Record::Call(phone_call) => phone_call.when,
+            Record::Line { at, .. } => *at,
+            Record::Unreachable { at } => *at,
+            Record::Reboot { at } => *at,
+            Record::Outage(outage) => outage.from,
+            Record::Probe { at, .. } => *at,
+            Record::SpeedTest(speed_test) => speed_test.at,
+        }
+    }
+}
+
+/// The bot's clock, in local time like the modem's call log.
+pub fn now() -> NaiveDateTime {
+    Local::now().naive_local()
+}
+
+pub fn load() -> Vec<Record> {
+    store::load_lines(FILE)
+}
+
+pub fn load_since(since: NaiveDateTime) -> Vec<Record> {
+    load()
+        .into_iter()
+        .filter(|record| record.at() >= since)
+        .collect()
+}
+
+pub fn append(record: &Record) {
+    if store::append(FILE, record).is_none() {
+        warn!("Couldn't record {:?}", record);
+    }
+}
+
+/// Every call in the history, oldest first.
+pub fn calls() -> Vec<PhoneCall> {
+    load()
+        .into_iter()
+        .filter_map(|record| match record {
+            Record::Call(phone_call) => Some(phone_call),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Records the calls the modem remembers, skipping the ones already `known`, which is
+/// kept up to date so the history needn't be read on every poll.
+pub fn record_calls(known: &mut Vec<PhoneCall>, phone_calls: &[PhoneCall]) {
+    for phone_call in new_calls(known, phone_calls) {
+        append(&Record::Call(phone_call.clone()));
+        known.push(phone_call);
+    }
+
+    // calls gone from the modem's log don't come back, so there is no need to remember them
+    if let Some(oldest) = phone_calls.iter().map(|phone_call| phone_call.when).min() {
+        known.retain(|phone_call| phone_call.when >= oldest);
+    }
+}
+
+fn new_calls(known: &[PhoneCall], phone_calls: &[PhoneCall]) -> Vec<PhoneCall> {
+    let mut phone_calls: Vec<PhoneCall> = phone_calls
+        .iter()
+        .filter(|phone_call| !known.contains(phone_call))
+        .cloned()
+        .collect();
+
+    // the modem lists the newest call first
+    phone_calls.sort_by_key(|phone_call| phone_call.when);
+    phone_calls
+}
+
+/// Forgets records older than `HISTORY_DAYS`, 90 by default.
+pub fn prune() {
+    let days = env::var("HISTORY_DAYS")
+        .ok()
+        .and_then(|days| days.parse().ok())
+        .unwrap_or(90);
+    let since = match cutoff(days, now()) {
+        Some(since) => since,
+        None => {
+            warn!(
+                "HISTORY_DAYS {} is too long, keeping the whole history",
+                days
+            );
+            return;
+        }
+    };
+    let records = load();
+    let kept = load_since(since);
+
+    if kept.len() < records.len() {
+        debug!("Pruning {} history records", records.len() - kept.len());
+
+        if store::save_lines(FILE, &kept).is_none() {
+            warn!("Couldn't prune the history.");
+        }
+    }
+}
+
+// anything too far back for chrono is refused rather than panicking
+fn cutoff(days: i64, now: NaiveDateTime) -> Option<NaiveDateTime> {
+    now.checked_sub_signed(Duration::try_days(days)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timm::caller::CallerId;
+
+    use super::*;
+
+    #[test]
+    fn test_only_new_calls_are_recorded() {
+        let old_call = PhoneCall {
+            who: CallerId::from("0612345678"),
+            when: now() - Duration::hours(2),
+            missed: false,
+        };
+        let new_call = PhoneCall {
+            who: CallerId::from("3331234567"),
+            when: now(),
+            missed: true,
+        };
+
+        let phone_calls = vec![new_call.clone(), old_call.clone()];
+
+        assert_eq!(new_calls(&[old_call], &phone_calls), vec![new_call]);
+    }
+
+    #[test]
+    fn test_record_round_trip() {
+        let record = Record::Call(PhoneCall {
+            who: CallerId::Anonymous,
+            when: now(),
+            missed: true,
+        });
+        let line = serde_json::to_string(&record).unwrap();
+
+        assert_eq!(serde_json::from_str::<Record>(&line).unwrap(), record);
+    }
+
+    #[test]
+    fn test_cutoff() {
+        let now = now();
+
+        assert_eq!(cutoff(90, now), Some(now - Duration::days(90)));
+        assert_eq!(cutoff(100_000_000, now), None);
+        assert_eq!(cutoff(i64::MAX, now), None);
+    }
+}
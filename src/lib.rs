@@ -1,4 +1,29 @@
+pub mod actions;
+pub mod bus;
+pub mod callstats;
+pub mod contacts;
+pub mod digest;
+pub mod escalation;
+pub mod event;
+pub mod export;
+pub mod health;
+pub mod history;
+pub mod monitor;
+pub mod notifier;
+pub mod notify;
+pub mod outage;
+pub mod polling;
+pub mod probe;
+pub mod quiet;
+pub mod rules;
+pub mod schedule;
+pub mod spam;
+pub mod speedtest;
+pub mod store;
+pub mod supervisor;
+pub mod timers;
 pub mod timm;
+pub mod trusted;
 
 #[macro_use]
 extern crate log;
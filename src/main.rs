@@ -1,5 +1,12 @@
 use std::env;
-use teloxide::{prelude::*, utils::command::BotCommands};
+use std::sync::Arc;
+use teloxide::{
+    dispatching::UpdateHandler,
+    prelude::*,
+    types::{ForceReply, InputFile, ParseMode},
+    utils::{command::BotCommands, html},
+};
+use tokio::sync::{broadcast::Receiver, Mutex};
 use tokio::time::{sleep, Duration};
 
 extern crate pretty_env_logger;
@@ -7,8 +14,33 @@ extern crate pretty_env_logger;
 extern crate log;
 
 extern crate callog_bot;
+use callog_bot::actions::{self, CallAction};
+use callog_bot::bus::Bus;
+use callog_bot::callstats::CallStats;
+use callog_bot::contacts::Contacts;
+use callog_bot::digest::{Digest, DigestSchedule};
+use callog_bot::event::Event;
+use callog_bot::export;
+use callog_bot::health::{self, Health};
+use callog_bot::history::{self, Record};
+use callog_bot::monitor::{
+    call_message, CallMonitor, DeviceMonitor, OutageMonitor, ProbeMonitor, SpeedMonitor,
+    TimerMonitor, VoipMonitor,
+};
+use callog_bot::notifier::Notifier;
+use callog_bot::notify;
+use callog_bot::outage;
+use callog_bot::polling::{Pacing, Trigger};
+use callog_bot::quiet::{Dnd, Priority};
+use callog_bot::schedule;
+use callog_bot::spam::Blocklist;
+use callog_bot::speedtest::Endpoint;
+use callog_bot::store;
+use callog_bot::supervisor::{self, supervise};
+use callog_bot::timers::{self, Timers};
 use callog_bot::timm;
-use callog_bot::timm::{calls::PhoneCall, stats::LineSpeed};
+use callog_bot::timm::{caller, calls::PhoneCall, modem::Modem, nat::Forward, wifi::Network};
+use callog_bot::trusted::TrustedDevices;
 
 //mod timm;
 //use timm::PhoneCall;
@@ -29,12 +61,50 @@ enum Command {
     All,
     #[command(description = "display current speed.")]
     Speed,
+    #[command(description = "measure the actual download and upload speed.")]
+    Speedtest,
     #[command(description = "reboot the modem.")]
     Reboot,
+    #[command(description = "block calls from a number.")]
+    Block(String),
+    #[command(description = "unblock a number.")]
+    Unblock(String),
+    #[command(description = "silence alerts for a while, e.g. /dnd 2h or /dnd off.")]
+    Dnd(String),
+    #[command(description = "let a number ring through quiet hours.")]
+    Vip(String),
+    #[command(description = "display or switch the Wi-Fi, as in /wifi off 8h.")]
+    Wifi(String),
+    #[command(description = "display or switch the guest Wi-Fi, as in /guestwifi on 2h.")]
+    Guestwifi(String),
+    #[command(description = "display the port-forwarding rules.")]
+    Ports,
+    #[command(description = "forward a port, as in /openport 2222 192.168.1.50:22 4h.")]
+    Openport(String),
+    #[command(description = "stop forwarding a port.")]
+    Closeport(String),
+    #[command(description = "display the devices on the network.")]
+    Devices,
+    #[command(description = "label a device, as in /trust <mac> <name>.")]
+    Trust(String),
+    #[command(description = "display the modem and internet outages.")]
+    Outages,
+    #[command(description = "display the modem's event log, as in /log 20.")]
+    Log(String),
+    #[command(description = "display the phone lines and the last call.")]
+    Phone,
+    #[command(description = "send the call history as a file, as in /export 30d ics.")]
+    Export(String),
+    #[command(description = "display who calls and when, as in /stats calls 30d.")]
+    Stats(String),
+    #[command(description = "display whether the monitors are working.")]
+    Status,
+    #[command(description = "check the calls and the line now.")]
+    Poll,
 }
 
 async fn list_all_calls(bot: Bot, chat_id: ChatId) {
-    if let Some(mut phone_calls) = timm::calls::download_calls().await {
+    if let Some(mut phone_calls) = timm::calls::download_calls(&Modem::from_env()).await {
         if phone_calls.is_empty() {
             if bot
                 .send_message(
@@ -49,12 +119,14 @@ async fn list_all_calls(bot: Bot, chat_id: ChatId) {
         } else {
             debug!("There are new calls");
 
+            let contacts = Contacts::load();
+
             phone_calls.reverse();
             for phone_call in &phone_calls {
                 debug!("{}", phone_call);
 
                 if bot
-                    .send_message(chat_id, format!("{}", phone_call))
+                    .send_message(chat_id, call_message(&contacts, phone_call))
                     .await
                     .is_err()
                 {
@@ -78,7 +150,7 @@ async fn list_all_calls(bot: Bot, chat_id: ChatId) {
 }
 
 async fn list_recent_calls(bot: Bot, chat_id: ChatId) {
-    let mut recent_phone_calls: Vec<PhoneCall> = timm::calls::download_calls()
+    let mut recent_phone_calls: Vec<PhoneCall> = timm::calls::download_calls(&Modem::from_env())
         .await
         .unwrap_or_default()
         .into_iter()
@@ -99,12 +171,14 @@ async fn list_recent_calls(bot: Bot, chat_id: ChatId) {
             warn!("Couldn't send list_recent_calls message.");
         }
     } else {
+        let contacts = Contacts::load();
+
         recent_phone_calls.reverse();
         for phone_call in &recent_phone_calls {
             debug!("{}", phone_call);
 
             if bot
-                .send_message(chat_id, format!("{}", phone_call))
+                .send_message(chat_id, call_message(&contacts, phone_call))
                 .await
                 .is_err()
             {
@@ -114,96 +188,239 @@ async fn list_recent_calls(bot: Bot, chat_id: ChatId) {
     }
 }
 
-async fn monitor_calls(bot: Bot, chat_id: ChatId) {
+async fn list_calls_from(bot: Bot, chat_id: ChatId, number: &str) {
+    let phone_calls: Vec<PhoneCall> = timm::calls::download_calls(&Modem::from_env())
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|phone_call| phone_call.who.key() == number)
+        .collect();
+
+    debug!("There are {} calls from {}.", phone_calls.len(), number);
+
+    let text = if phone_calls.is_empty() {
+        format!("There are no calls from {} in memory.", number)
+    } else {
+        phone_calls.iter().fold(
+            format!("{} calls from {}:", phone_calls.len(), number),
+            |text, phone_call| {
+                format!("{}\n👉 {}", text, phone_call.when.format("%H:%M on %-d %b"))
+            },
+        )
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send list_calls_from message.");
+    }
+}
+
+async fn monitor_calls(bus: Bus, health: Health, mut poll: Trigger) {
     info!("Starting - monitor_calls");
 
-    let mut last_call: Option<PhoneCall> = None;
+    let mut monitor = CallMonitor::resume(Modem::from_env(), bus);
+    let pacing = Pacing::from_env(Duration::from_secs(60), Duration::from_secs(15));
+    let mut failures = 0;
+    health.watch("monitor_calls");
 
     loop {
         info!("Checking calls");
 
-        let latest_calls = timm::calls::download_calls()
-            .await
-            .and_then(|calls| timm::calls::get_new_calls(&last_call, calls));
+        let result = monitor.check().await;
+        failures = if result.is_ok() { 0 } else { failures + 1 };
+        health.record("monitor_calls", history::now(), result);
 
-        if let Some(mut latest_calls) = latest_calls {
-            debug!("There are new calls");
+        let wait = pacing.next(monitor.recently_called(history::now()), failures);
+        debug!("Checking calls again in {:?}", wait);
+        poll.wait(wait).await;
+    }
+}
 
-            latest_calls.reverse();
-            for phone_call in &latest_calls {
-                debug!("{}", phone_call);
+async fn monitor_speed(bus: Bus, health: Health, mut poll: Trigger) {
+    info!("Starting - monitor_speed");
 
-                if bot
-                    .send_message(chat_id, format!("{}", phone_call))
-                    .await
-                    .is_err()
-                {
-                    warn!("Couldn't send monitor_calls message.");
-                }
-            }
+    let mut monitor = SpeedMonitor::resume(Modem::from_env(), bus);
+    let pacing = Pacing::from_env(Duration::from_secs(5 * 60), Duration::from_secs(60));
+    let mut failures = 0;
+    health.watch("monitor_speed");
+
+    loop {
+        info!("Checking stats");
+
+        let result = monitor.check().await;
+        failures = if result.is_ok() { 0 } else { failures + 1 };
+        health.record("monitor_speed", history::now(), result);
+
+        let wait = pacing.next(monitor.is_degraded(), failures);
+        debug!("Checking stats again in {:?}", wait);
+        poll.wait(wait).await;
+    }
+}
+
+async fn notify_events(bot: Bot, chat_id: ChatId, bus: Bus, events: Arc<Mutex<Receiver<Event>>>) {
+    info!("Starting - notify_events");
+
+    let mut events = events.lock().await;
+    Notifier::new(Modem::from_env(), bus)
+        .run(&bot, chat_id, &mut events)
+        .await;
+}
 
-            if let Some(call) = Some(latest_calls.last().cloned()) {
-                last_call = call;
+async fn monitor_outages(bus: Bus) {
+    info!("Starting - monitor_outages");
+
+    let mut monitor = OutageMonitor::new(Modem::from_env(), bus);
+
+    loop {
+        info!("Checking connectivity");
+
+        monitor.check().await;
+
+        sleep(Duration::from_secs(60)).await;
+    }
+}
+
+async fn monitor_probes(bus: Bus) {
+    info!("Starting - monitor_probes");
+
+    let mut monitor = ProbeMonitor::new(&Modem::from_env(), bus);
+
+    loop {
+        info!("Probing connection");
+
+        monitor.check().await;
+
+        sleep(Duration::from_secs(60)).await;
+    }
+}
+
+async fn monitor_devices(bus: Bus, health: Health) {
+    info!("Starting - monitor_devices");
+
+    // new-device alerts are opt in, with DEVICE_ALERTS=on
+    if !env::var("DEVICE_ALERTS").is_ok_and(|alerts| alerts.trim() == "on") {
+        debug!("Device alerts are off");
+        std::future::pending::<()>().await;
+    }
+
+    let mut monitor = DeviceMonitor::new(Modem::from_env(), bus);
+    health.watch("monitor_devices");
+
+    loop {
+        info!("Checking devices");
+
+        let result = monitor.check().await;
+        health.record("monitor_devices", history::now(), result);
+
+        sleep(Duration::from_secs(5 * 60)).await;
+    }
+}
+
+async fn monitor_voip(bus: Bus, health: Health) {
+    info!("Starting - monitor_voip");
+
+    let mut monitor = VoipMonitor::new(Modem::from_env(), bus);
+    health.watch("monitor_voip");
+
+    loop {
+        info!("Checking phone lines");
+
+        let result = monitor.check().await;
+        health.record("monitor_voip", history::now(), result);
+
+        sleep(Duration::from_secs(60)).await;
+    }
+}
+
+async fn monitor_health(bot: Bot, chat_id: ChatId, health: Health) {
+    info!("Starting - monitor_health");
+
+    let window = health::window_from_env();
+
+    loop {
+        sleep(Duration::from_secs(60)).await;
+
+        for text in health.heartbeat(history::now(), window) {
+            if notify::alert(&bot, chat_id, text, None, Priority::Normal)
+                .await
+                .is_none()
+            {
+                warn!("Couldn't send monitor_health message.");
             }
-        } else {
-            warn!("No calls found.")
         }
+    }
+}
+
+async fn monitor_timers(bus: Bus) {
+    info!("Starting - monitor_timers");
+
+    let mut monitor = TimerMonitor::new(Modem::from_env(), bus);
+
+    loop {
+        monitor.check().await;
 
         sleep(Duration::from_secs(60)).await;
     }
 }
 
-async fn monitor_speed(bot: Bot, chat_id: ChatId) {
-    info!("Starting - monitor_speed");
+async fn monitor_held(bot: Bot) {
+    info!("Starting - monitor_held");
+
+    loop {
+        notify::release_held(&bot).await;
+
+        sleep(Duration::from_secs(60)).await;
+    }
+}
 
-    let mut last_speed = LineSpeed::Normal;
-    let mut last_ip = String::new();
+async fn monitor_history() {
+    info!("Starting - monitor_history");
 
     loop {
-        info!("Checking stats");
+        history::prune();
 
-        if let Some(stats) = timm::stats::download_stats().await {
-            if stats.speed != last_speed {
-                if bot
-                    .send_message(chat_id, format!("{}", stats.speed))
-                    .await
-                    .is_err()
-                {
-                    warn!("Couldn't send monitor_speed (speed) message.");
-                }
+        sleep(Duration::from_secs(24 * 60 * 60)).await;
+    }
+}
 
-                debug!("{}", stats.speed);
-                last_speed = stats.speed;
-            } else {
-                debug!("Skipping same speed state");
-            }
+async fn monitor_digest(bot: Bot, chat_id: ChatId) {
+    info!("Starting - monitor_digest");
 
-            // NOTE: I am only adding this temporarily, because I want to see how often the IP address
-            // changes throughout the day, and perhaps the connection is automatically resetting
-            if stats.ip != last_ip {
-                if bot
-                    .send_message(chat_id, format!("IP is {}", stats.ip))
-                    .await
-                    .is_err()
-                {
-                    warn!("Couldn't send monitor_speed (ip) message.");
-                }
+    let schedule = DigestSchedule::from_env();
+    if schedule.is_empty() {
+        debug!("No digests are scheduled");
+    }
 
-                debug!("{}", stats.ip);
-                last_ip = stats.ip;
-            } else {
-                debug!("Skipping same ip");
+    let mut last_check = history::now();
+
+    loop {
+        let now = history::now();
+
+        for period in schedule.due(last_check, now) {
+            info!("Sending {:?} digest", period);
+
+            let digest = Digest::build(period, &history::load(), now);
+
+            if notify::alert(
+                &bot,
+                chat_id,
+                digest.message(&Contacts::load()),
+                None,
+                Priority::Normal,
+            )
+            .await
+            .is_none()
+            {
+                warn!("Couldn't send monitor_digest message.");
             }
-        } else {
-            warn!("Problem getting stats");
         }
 
-        sleep(Duration::from_secs(5 * 60)).await;
+        last_check = now;
+        sleep(Duration::from_secs(60)).await;
     }
 }
 
 async fn list_speed(bot: Bot, chat_id: ChatId) {
-    if let Some(stats) = timm::stats::download_stats().await {
+    if let Some(stats) = timm::stats::download_stats(&Modem::from_env()).await {
         if bot
             .send_message(chat_id, format!("{}", stats))
             .await
@@ -216,8 +433,43 @@ async fn list_speed(bot: Bot, chat_id: ChatId) {
     }
 }
 
-async fn reboot(bot: Bot, chat_id: ChatId) {
-    if timm::tools::reboot().await.is_some() {
+async fn speedtest(bot: Bot, chat_id: ChatId) {
+    let endpoint = Endpoint::from_env();
+
+    if bot
+        .send_message(
+            chat_id,
+            format!("Testing the speed against {}...", endpoint.url),
+        )
+        .await
+        .is_err()
+    {
+        warn!("Couldn't send speedtest message.");
+    }
+
+    let speed_test = endpoint.run(history::now()).await;
+    let stats = timm::stats::download_stats(&Modem::from_env()).await;
+    history::append(&Record::SpeedTest(speed_test.clone()));
+
+    if bot
+        .send_message(chat_id, speed_test.message(stats.as_ref()))
+        .await
+        .is_err()
+    {
+        warn!("Couldn't send speedtest message.");
+    }
+}
+
+async fn reboot(bot: Bot, chat_id: ChatId, bus: &Bus) {
+    let at = history::now();
+
+    if timm::tools::reboot(&Modem::from_env()).await.is_some() {
+        history::append(&Record::Reboot { at });
+        bus.publish(Event::RebootIssued {
+            at,
+            by: "/reboot".to_string(),
+        });
+
         if bot
             .send_message(chat_id, "The modem should be rebooting.")
             .await
@@ -234,9 +486,479 @@ async fn reboot(bot: Bot, chat_id: ChatId) {
     }
 }
 
-async fn answer(bot: Bot, message: Message, command: Command) -> ResponseResult<()> {
-    let chat_id = if let Ok(chat_id) = env::var("CHAT_ID").expect("CHAT_ID must be set").parse() {
-        ChatId(chat_id)
+// anything else would be kept as an unavailable caller, and match all of them
+fn not_a_number(command: &str, number: &str) -> String {
+    format!(
+        "{1} isn't a phone number. Use /{0} <number>, or /{0} anonymous or /{0} unavailable for callers without one.",
+        command,
+        number.trim()
+    )
+}
+
+async fn block(bot: Bot, chat_id: ChatId, number: &str) {
+    let text = if number.trim().is_empty() {
+        "Which number should I block?".to_string()
+    } else if caller::parse_command(number).is_none() {
+        not_a_number("block", number)
+    } else {
+        let mut blocklist = Blocklist::load();
+        blocklist.insert(number);
+
+        if blocklist.save().is_some() {
+            format!("Blocked {}.", number.trim())
+        } else {
+            "Problem saving the blocklist!".to_string()
+        }
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send block message.");
+    }
+}
+
+async fn unblock(bot: Bot, chat_id: ChatId, number: &str) {
+    let mut blocklist = Blocklist::load();
+
+    let text = if caller::parse_command(number).is_none() {
+        not_a_number("unblock", number)
+    } else if !blocklist.remove(number) {
+        format!("{} isn't blocked.", number.trim())
+    } else if blocklist.save().is_some() {
+        format!("Unblocked {}.", number.trim())
+    } else {
+        "Problem saving the blocklist!".to_string()
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send unblock message.");
+    }
+}
+
+async fn dnd(bot: Bot, chat_id: ChatId, duration: &str) {
+    let now = history::now();
+    let mut dnd = Dnd::load();
+
+    let text = if duration.trim().is_empty() {
+        match dnd.until(chat_id.0, now) {
+            Some(until) => format!("Do not disturb until {}.", until.format("%H:%M on %-d %b")),
+            None => "Do not disturb is off.".to_string(),
+        }
+    } else if duration.trim().eq_ignore_ascii_case("off") {
+        dnd.stop(chat_id.0);
+
+        if dnd.save().is_some() {
+            "Do not disturb is off.".to_string()
+        } else {
+            "Problem saving do not disturb!".to_string()
+        }
+    } else if let Some(duration) = schedule::parse_duration(duration) {
+        match dnd.start(chat_id.0, now, duration) {
+            Some(until) if dnd.save().is_some() => {
+                format!("Do not disturb until {}.", until.format("%H:%M on %-d %b"))
+            }
+            Some(_) => "Problem saving do not disturb!".to_string(),
+            None => "That's too long for do not disturb.".to_string(),
+        }
+    } else {
+        "Try /dnd 2h, /dnd 30m or /dnd off.".to_string()
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send dnd message.");
+    }
+}
+
+async fn list_outages(bot: Bot, chat_id: ChatId) {
+    let since = history::now() - chrono::Duration::weeks(1);
+    let text = outage::list_message(&outage::load_since(since), outage::current().as_ref());
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send list_outages message.");
+    }
+}
+
+async fn call_stats(bot: Bot, chat_id: ChatId, args: &str) {
+    let mut words = args.split_whitespace();
+    let period = match (words.next(), words.next(), words.next()) {
+        (Some("calls"), None, None) => Some(chrono::Duration::days(30)),
+        (Some("calls"), Some(period), None) => schedule::parse_duration(period),
+        _ => None,
+    };
+
+    let sent = match period {
+        Some(period) => {
+            let to = history::now();
+            let phone_calls = history::calls();
+            // longer than chrono can go back is the same as the whole history
+            let from = to.checked_sub_signed(period).unwrap_or_else(|| {
+                phone_calls
+                    .iter()
+                    .map(|phone_call| phone_call.when)
+                    .min()
+                    .unwrap_or(to)
+            });
+            let stats = CallStats::build(&phone_calls, from, to);
+
+            bot.send_message(chat_id, stats.message(&Contacts::load()))
+                .parse_mode(ParseMode::Html)
+                .await
+                .is_ok()
+        }
+        None => bot
+            .send_message(chat_id, "Use /stats calls [period], as in /stats calls 7d.")
+            .await
+            .is_ok(),
+    };
+
+    if !sent {
+        warn!("Couldn't send call_stats message.");
+    }
+}
+
+async fn status(bot: Bot, chat_id: ChatId, health: &Health) {
+    if bot
+        .send_message(chat_id, health.status(history::now(), store::size()))
+        .await
+        .is_err()
+    {
+        warn!("Couldn't send status message.");
+    }
+}
+
+async fn export_calls(bot: Bot, chat_id: ChatId, args: &str) {
+    let (period, format) = match export::parse_args(args) {
+        Some(args) => args,
+        None => {
+            if bot
+                .send_message(
+                    chat_id,
+                    "Use /export [period] [csv|json|ics], as in /export 30d ics.",
+                )
+                .await
+                .is_err()
+            {
+                warn!("Couldn't send export_calls message.");
+            }
+            return;
+        }
+    };
+
+    // longer than chrono can go back is the same as the whole history
+    let since = period.and_then(|period| history::now().checked_sub_signed(period));
+    let mut phone_calls: Vec<PhoneCall> = history::load()
+        .into_iter()
+        .filter_map(|record| match record {
+            Record::Call(phone_call) if since.is_none_or(|since| phone_call.when >= since) => {
+                Some(phone_call)
+            }
+            _ => None,
+        })
+        .collect();
+    phone_calls.sort_by_key(|phone_call| phone_call.when);
+
+    let sent = if phone_calls.is_empty() {
+        bot.send_message(chat_id, "There are no calls to export.")
+            .await
+            .is_ok()
+    } else {
+        let file = InputFile::memory(export::export(format, &phone_calls, &Contacts::load()))
+            .file_name(format!(
+                "calls-{}.{}",
+                history::now().format("%Y%m%d"),
+                format.extension()
+            ));
+
+        bot.send_document(chat_id, file)
+            .caption(format!("📇 {} calls", phone_calls.len()))
+            .await
+            .is_ok()
+    };
+
+    if !sent {
+        warn!("Couldn't send export_calls message.");
+    }
+}
+
+async fn phone(bot: Bot, chat_id: ChatId) {
+    let modem = Modem::from_env();
+
+    let mut lines = match timm::voip::download_lines(&modem).await {
+        Some(lines) if lines.is_empty() => vec!["No phone lines found.".to_string()],
+        Some(lines) => lines.iter().map(|line| line.to_string()).collect(),
+        None => vec!["Problem getting the phone lines!".to_string()],
+    };
+
+    // the modem lists the newest call first
+    lines.push(match timm::calls::download_calls(&modem).await {
+        Some(phone_calls) => match phone_calls.first() {
+            Some(phone_call) => format!(
+                "Last call {}:\n{}",
+                phone_call.when.format("%-d %b %H:%M"),
+                call_message(&Contacts::load(), phone_call)
+            ),
+            None => "No calls in memory.".to_string(),
+        },
+        None => "Problem getting the calls!".to_string(),
+    });
+
+    if bot.send_message(chat_id, lines.join("\n")).await.is_err() {
+        warn!("Couldn't send phone message.");
+    }
+}
+
+async fn list_events(bot: Bot, chat_id: ChatId, count: &str) {
+    let count = if count.trim().is_empty() {
+        Some(10)
+    } else {
+        count.trim().parse::<usize>().ok()
+    };
+
+    let text = match count {
+        Some(count) => match timm::events::download_events(&Modem::from_env()).await {
+            Some(events) if events.is_empty() => "The event log is empty.".to_string(),
+            Some(events) => events
+                .iter()
+                .take(count)
+                .map(|event| event.to_string())
+                .collect::<Vec<String>>()
+                .join("\n"),
+            None => "Problem getting the event log!".to_string(),
+        },
+        None => "How many events? Use /log or /log 20.".to_string(),
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send list_events message.");
+    }
+}
+
+async fn wifi(bot: Bot, chat_id: ChatId, network: Network, args: &str) {
+    let modem = Modem::from_env();
+
+    let text = if args.trim().is_empty() {
+        let networks = match network {
+            Network::Main => vec![Network::Main, Network::Guest],
+            Network::Guest => vec![Network::Guest],
+        };
+        let mut lines = Vec::new();
+
+        for network in networks {
+            lines.push(match timm::wifi::download_status(&modem, network).await {
+                Some(status) => status.to_string(),
+                None => format!("Problem getting the {} status!", network),
+            });
+        }
+
+        lines.join("\n")
+    } else if let Some((enabled, duration)) = timers::parse_switch(args) {
+        let on_off = |enabled| if enabled { "on" } else { "off" };
+        let until = duration.map(|duration| history::now().checked_add_signed(duration));
+
+        if until == Some(None) {
+            "That's too long, use a shorter duration.".to_string()
+        } else if timm::wifi::set_enabled(&modem, network, enabled)
+            .await
+            .is_some()
+        {
+            let mut timers = Timers::load();
+
+            let text = match until.flatten() {
+                Some(until) => {
+                    timers.set_wifi(network, !enabled, until);
+
+                    format!(
+                        "{} is {} until {}.",
+                        network,
+                        on_off(enabled),
+                        until.format("%H:%M")
+                    )
+                }
+                None => {
+                    timers.cancel_wifi(network);
+
+                    format!("{} is {}.", network, on_off(enabled))
+                }
+            };
+
+            if timers.save().is_none() {
+                warn!("Couldn't save the timers.");
+            }
+
+            text
+        } else {
+            format!("Problem switching the {} {}!", network, on_off(enabled))
+        }
+    } else {
+        "Use on or off, and optionally for how long, such as /guestwifi on 2h.".to_string()
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send wifi message.");
+    }
+}
+
+async fn list_ports(bot: Bot, chat_id: ChatId) {
+    let text = match timm::nat::download_forwards(&Modem::from_env()).await {
+        Some(forwards) if forwards.is_empty() => "No ports are forwarded.".to_string(),
+        Some(forwards) => {
+            let timers = Timers::load();
+
+            forwards
+                .iter()
+                .map(|forward| match timers.port(forward.external) {
+                    Some(expires) => {
+                        format!("{}\n⏳ until {}", forward, expires.format("%-d %b %H:%M"))
+                    }
+                    None => forward.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+        None => "Problem getting the port-forwarding rules!".to_string(),
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send list_ports message.");
+    }
+}
+
+async fn open_port(bot: Bot, chat_id: ChatId, args: &str) {
+    let (rule, duration) = timers::split_duration(args);
+    // ports opened from the bot are never left open for good
+    let duration = duration.unwrap_or_else(|| chrono::Duration::days(1));
+
+    let expires = history::now().checked_add_signed(duration);
+
+    let text = match (Forward::try_from(rule), expires) {
+        (Ok(_), None) => "That's too long, use a shorter duration.".to_string(),
+        (Ok(forward), Some(expires)) => {
+            if timm::nat::add_forward(&Modem::from_env(), &forward)
+                .await
+                .is_some()
+            {
+                let mut timers = Timers::load();
+                timers.set_port(forward.external, expires);
+
+                if timers.save().is_none() {
+                    warn!("Couldn't save the timers.");
+                }
+
+                format!("{}\n⏳ until {}", forward, expires.format("%-d %b %H:%M"))
+            } else {
+                format!("Problem forwarding port {}!", forward.external)
+            }
+        }
+        (Err(_), _) => "Which port? Use /openport <port>[/udp] <ip>:<port> [duration].".to_string(),
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send open_port message.");
+    }
+}
+
+async fn close_port(bot: Bot, chat_id: ChatId, port: &str) {
+    let text = match port.trim().parse() {
+        Ok(external) => {
+            if timm::nat::remove_forward(&Modem::from_env(), external)
+                .await
+                .is_some()
+            {
+                let mut timers = Timers::load();
+                if timers.cancel_port(external) && timers.save().is_none() {
+                    warn!("Couldn't save the timers.");
+                }
+
+                format!("🔒 Port {} closed.", external)
+            } else {
+                format!("Problem closing port {}!", external)
+            }
+        }
+        Err(_) => "Which port? Use /closeport <port>.".to_string(),
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send close_port message.");
+    }
+}
+
+async fn list_devices(bot: Bot, chat_id: ChatId) {
+    let text = match timm::devices::download_devices(&Modem::from_env()).await {
+        Some(devices) if devices.is_empty() => "No devices are connected.".to_string(),
+        Some(devices) => {
+            let trusted = TrustedDevices::load();
+
+            devices
+                .iter()
+                .map(|device| trusted.describe(device))
+                .collect::<Vec<String>>()
+                .join("\n")
+        }
+        None => "Problem getting the devices!".to_string(),
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send list_devices message.");
+    }
+}
+
+async fn trust(bot: Bot, chat_id: ChatId, args: &str) {
+    let text = match args.trim().split_once(' ') {
+        Some((mac, name)) if !name.trim().is_empty() => {
+            let mut trusted = TrustedDevices::load();
+
+            match trusted.trust(mac, name) {
+                None => format!("{} isn't a MAC address.", mac),
+                Some(_) if trusted.save().is_none() => "Problem saving the device!".to_string(),
+                Some(_) => format!("{} is now {}.", mac, name.trim()),
+            }
+        }
+        _ => "Which device? Use /trust <mac> <name>.".to_string(),
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send trust message.");
+    }
+}
+
+async fn vip(bot: Bot, chat_id: ChatId, number: &str) {
+    let text = if number.trim().is_empty() {
+        "Which number should ring through quiet hours?".to_string()
+    } else if caller::parse_command(number).is_none() {
+        not_a_number("vip", number)
+    } else {
+        let mut contacts = Contacts::load();
+        let is_vip = contacts.toggle_vip(number);
+
+        match (contacts.save(), is_vip) {
+            (None, _) => "Problem saving the contact!".to_string(),
+            (Some(_), true) => format!("{} will ring through quiet hours.", number.trim()),
+            (Some(_), false) => format!("{} is no longer a VIP.", number.trim()),
+        }
+    };
+
+    if bot.send_message(chat_id, text).await.is_err() {
+        warn!("Couldn't send vip message.");
+    }
+}
+
+fn allowed_chat_id() -> Option<ChatId> {
+    env::var("CHAT_ID")
+        .expect("CHAT_ID must be set")
+        .parse()
+        .ok()
+        .map(ChatId)
+}
+
+async fn answer(
+    bot: Bot,
+    message: Message,
+    command: Command,
+    bus: Bus,
+    health: Health,
+    poll: Trigger,
+) -> ResponseResult<()> {
+    let chat_id = if let Some(chat_id) = allowed_chat_id() {
+        chat_id
     } else {
         return Ok(());
     };
@@ -271,14 +993,190 @@ async fn answer(bot: Bot, message: Message, command: Command) -> ResponseResult<
         Command::Speed => {
             list_speed(bot.clone(), chat_id).await;
         }
+        Command::Speedtest => {
+            speedtest(bot.clone(), chat_id).await;
+        }
         Command::Reboot => {
-            reboot(bot.clone(), chat_id).await;
+            reboot(bot.clone(), chat_id, &bus).await;
+        }
+        Command::Block(number) => {
+            block(bot.clone(), chat_id, &number).await;
+        }
+        Command::Unblock(number) => {
+            unblock(bot.clone(), chat_id, &number).await;
+        }
+        Command::Dnd(duration) => {
+            dnd(bot.clone(), chat_id, &duration).await;
+        }
+        Command::Vip(number) => {
+            vip(bot.clone(), chat_id, &number).await;
+        }
+        Command::Wifi(args) => {
+            wifi(bot.clone(), chat_id, Network::Main, &args).await;
+        }
+        Command::Guestwifi(args) => {
+            wifi(bot.clone(), chat_id, Network::Guest, &args).await;
+        }
+        Command::Ports => {
+            list_ports(bot.clone(), chat_id).await;
+        }
+        Command::Openport(args) => {
+            open_port(bot.clone(), chat_id, &args).await;
+        }
+        Command::Closeport(port) => {
+            close_port(bot.clone(), chat_id, &port).await;
+        }
+        Command::Devices => {
+            list_devices(bot.clone(), chat_id).await;
+        }
+        Command::Trust(args) => {
+            trust(bot.clone(), chat_id, &args).await;
+        }
+        Command::Outages => {
+            list_outages(bot.clone(), chat_id).await;
+        }
+        Command::Log(count) => {
+            list_events(bot.clone(), chat_id, &count).await;
+        }
+        Command::Phone => {
+            phone(bot.clone(), chat_id).await;
+        }
+        Command::Export(args) => {
+            export_calls(bot.clone(), chat_id, &args).await;
+        }
+        Command::Stats(args) => {
+            call_stats(bot.clone(), chat_id, &args).await;
+        }
+        Command::Status => {
+            status(bot.clone(), chat_id, &health).await;
+        }
+        Command::Poll => {
+            poll.fire();
+
+            if bot
+                .send_message(chat_id, "Checking the modem now.")
+                .await
+                .is_err()
+            {
+                warn!("Couldn't send poll message.");
+            }
         }
     };
 
     Ok(())
 }
 
+async fn answer_callback(bot: Bot, query: CallbackQuery) -> ResponseResult<()> {
+    let chat_id = if let Some(chat_id) = allowed_chat_id() {
+        chat_id
+    } else {
+        return Ok(());
+    };
+
+    if query.message.as_ref().map(|message| message.chat.id) != Some(chat_id) {
+        bot.answer_callback_query(query.id)
+            .text("I shouldn't speak to strangers.")
+            .await?;
+        debug!("I shouldn't talk to strangers: {}", query.from.id);
+
+        return Ok(());
+    }
+
+    let action = query
+        .data
+        .as_deref()
+        .and_then(|data| CallAction::try_from(data).ok());
+
+    match action {
+        Some(CallAction::Save(number)) => {
+            bot.answer_callback_query(query.id).await?;
+            bot.send_message(chat_id, actions::save_prompt(&number))
+                .reply_markup(ForceReply::new())
+                .await?;
+        }
+        Some(CallAction::Spam(number)) => {
+            let mut blocklist = Blocklist::load();
+            blocklist.insert(&number);
+            blocklist.report(&number);
+
+            let text = if blocklist.save().is_some() {
+                format!("Blocked {} and marked it as spam.", number)
+            } else {
+                "Problem saving the blocklist!".to_string()
+            };
+
+            bot.answer_callback_query(query.id).text(text).await?;
+        }
+        Some(CallAction::History(number)) => {
+            bot.answer_callback_query(query.id).await?;
+            list_calls_from(bot.clone(), chat_id, &number).await;
+        }
+        Some(CallAction::Copy(number)) => {
+            bot.answer_callback_query(query.id).await?;
+            bot.send_message(chat_id, format!("<code>{}</code>", html::escape(&number)))
+                .parse_mode(ParseMode::Html)
+                .await?;
+        }
+        None => {
+            warn!("Unknown callback data: {:?}", query.data);
+            bot.answer_callback_query(query.id).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn save_contact(bot: Bot, message: Message) -> ResponseResult<()> {
+    if Some(message.chat.id) != allowed_chat_id() {
+        return Ok(());
+    }
+
+    let number = message
+        .reply_to_message()
+        .and_then(|prompt| prompt.text())
+        .and_then(actions::parse_save_prompt);
+
+    if let (Some(number), Some(name)) = (number, message.text()) {
+        if caller::parse_command(number).is_none() {
+            bot.send_message(message.chat.id, format!("{} isn't a phone number.", number))
+                .await?;
+            return Ok(());
+        }
+
+        let mut contacts = Contacts::load();
+        contacts.insert(number, name);
+
+        let text = if contacts.save().is_some() {
+            format!("Saved {} as {}.", number, name.trim())
+        } else {
+            "Problem saving the contact!".to_string()
+        };
+
+        bot.send_message(message.chat.id, text).await?;
+    }
+
+    Ok(())
+}
+
+async fn handle_updates(bot: Bot, bus: Bus, health: Health, poll: Trigger) {
+    Dispatcher::builder(bot, handler())
+        .dependencies(dptree::deps![bus, health, poll])
+        .build()
+        .dispatch()
+        .await;
+}
+
+fn handler() -> UpdateHandler<teloxide::RequestError> {
+    dptree::entry()
+        .branch(
+            Update::filter_message()
+                .filter_command::<Command>()
+                .endpoint(answer),
+        )
+        .branch(Update::filter_message().endpoint(save_contact))
+        .branch(Update::filter_callback_query().endpoint(answer_callback))
+}
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     pretty_env_logger::init();
@@ -287,25 +1185,61 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let chat_id: ChatId = ChatId(env::var("CHAT_ID").expect("CHAT_ID must be set").parse()?);
 
     let bot = Bot::from_env();
-    let bot_calls_clone = bot.clone();
-    let bot_speed_clone = bot.clone();
-    // let bot_clone_clone = bot.clone();
-    // let handler = Command::repl(bot.clone(), answer);
+    let health = Health::new(history::now());
+    let poll = Trigger::default();
+    let bus = Bus::new();
+    // subscribed before any monitor runs, so no event is missed
+    let events = Arc::new(Mutex::new(bus.subscribe()));
 
     tokio::select! {
-      _ = async move {loop {
-        monitor_calls(bot_calls_clone.clone(), chat_id).await;
-        warn!("Restarting monitor_calls");
-      }} => {},
-      _ = async move {loop {
-        monitor_speed(bot_speed_clone.clone(), chat_id).await;
-        warn!("Restarting monitor_speed");
-      }} => {},
-      _ = async {loop {
-        Command::repl(bot.clone(), answer).await;
-        warn!("Restarting handler");
-      }} => {},
+      _ = supervise("monitor_calls", &bot, chat_id, || {
+        monitor_calls(bus.clone(), health.clone(), poll.clone())
+      }) => {},
+      _ = supervise("monitor_speed", &bot, chat_id, || {
+        monitor_speed(bus.clone(), health.clone(), poll.clone())
+      }) => {},
+      _ = supervise("notify_events", &bot, chat_id, || {
+        notify_events(bot.clone(), chat_id, bus.clone(), events.clone())
+      }) => {},
+      _ = supervise("monitor_outages", &bot, chat_id, || {
+        monitor_outages(bus.clone())
+      }) => {},
+      _ = supervise("monitor_probes", &bot, chat_id, || {
+        monitor_probes(bus.clone())
+      }) => {},
+      _ = supervise("monitor_devices", &bot, chat_id, || {
+        monitor_devices(bus.clone(), health.clone())
+      }) => {},
+      _ = supervise("monitor_timers", &bot, chat_id, || {
+        monitor_timers(bus.clone())
+      }) => {},
+      _ = supervise("monitor_voip", &bot, chat_id, || {
+        monitor_voip(bus.clone(), health.clone())
+      }) => {},
+      _ = supervise("monitor_health", &bot, chat_id, || {
+        monitor_health(bot.clone(), chat_id, health.clone())
+      }) => {},
+      _ = supervise("monitor_held", &bot, chat_id, || {
+        monitor_held(bot.clone())
+      }) => {},
+      _ = supervise("monitor_history", &bot, chat_id, || {
+        monitor_history()
+      }) => {},
+      _ = supervise("monitor_digest", &bot, chat_id, || {
+        monitor_digest(bot.clone(), chat_id)
+      }) => {},
+      _ = supervise("handler", &bot, chat_id, || {
+        handle_updates(bot.clone(), bus.clone(), health.clone(), poll.clone())
+      }) => {},
+      _ = supervisor::shutdown_signal() => {},
     }
 
+    info!("Shutting down");
+
+    // the monitors have stopped, and what they published last may not have been sent yet
+    Notifier::new(Modem::from_env(), bus)
+        .drain(&bot, chat_id, &mut *events.lock().await)
+        .await;
+
     Ok(())
 }
@@ -0,0 +1,545 @@
+use crate::bus::Bus;
+use crate::contacts::Contacts;
+use crate::event::Event;
+use crate::history::{self, Record};
+use crate::outage::{self, Cause, Outage};
+use crate::probe::{ProbeResult, Prober, Rule};
+use crate::store;
+use crate::timers::Timers;
+use crate::timm::calls::{self, PhoneCall};
+use crate::timm::devices;
+use crate::timm::events;
+use crate::timm::layout::{self, Mismatch};
+use crate::timm::modem::Modem;
+use crate::timm::nat;
+use crate::timm::stats::{self, LineSpeed};
+use crate::timm::voip;
+use crate::timm::wifi;
+use crate::trusted::TrustedDevices;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+// the page layout changes already reported, by page
+const LAYOUT_FILE: &str = "layout.json";
+
+// how far back, in minutes, the event log is read for a change
+const EVENT_WINDOW: i64 = 10;
+
+// what the call and line monitors last saw, so a restart doesn't announce it again
+const CALL_STATE_FILE: &str = "call_monitor.json";
+const LINE_STATE_FILE: &str = "speed_monitor.json";
+
+// for how long, in minutes, another call is likely after one
+const CALLS_BUSY: i64 = 10;
+
+pub fn call_message(contacts: &Contacts, phone_call: &PhoneCall) -> String {
+    match contacts.name(&phone_call.who.key()) {
+        Some(name) => format!("{}\n👤 {}", phone_call, name),
+        None => format!("{}", phone_call),
+    }
+}
+
+/// Publishes a change when a modem page stops looking the way the parsers expect, with
+/// a copy of the page. Each change is published until it's reported, and then once, until
+/// the page can be read again.
+fn check_layout(bus: &Bus, page: &str, html: &str, mismatch: Option<Mismatch>) {
+    let mut reported: BTreeMap<String, String> = store::load(LAYOUT_FILE);
+
+    let mismatch = match mismatch {
+        Some(mismatch) => mismatch.to_string(),
+        None => {
+            if reported.remove(page).is_some() {
+                info!("The {} layout can be read again", page);
+                if store::save(LAYOUT_FILE, &reported).is_none() {
+                    warn!("Couldn't save the reported layout changes.");
+                }
+            }
+            return;
+        }
+    };
+
+    if reported.get(page) == Some(&mismatch) {
+        debug!("Already reported the {} layout change", page);
+        return;
+    }
+
+    bus.publish(Event::LayoutChanged {
+        page: page.to_string(),
+        copy: store::save_text(&format!("layout/{}", page), html),
+        mismatch,
+    });
+}
+
+/// Notes that the chat heard about a layout change, so it isn't published again.
+pub fn set_layout_reported(page: &str, mismatch: &str) -> Option<()> {
+    let mut reported: BTreeMap<String, String> = store::load(LAYOUT_FILE);
+    reported.insert(page.to_string(), mismatch.to_string());
+
+    store::save(LAYOUT_FILE, &reported)
+}
+
+/// Publishes the calls that arrived since the last check.
+pub struct CallMonitor {
+    modem: Modem,
+    bus: Bus,
+    last_call: Option<PhoneCall>,
+    // the calls already in the history, read from it on the first check only
+    recorded: Option<Vec<PhoneCall>>,
+}
+
+impl CallMonitor {
+    pub fn new(modem: Modem, bus: Bus) -> Self {
+        CallMonitor {
+            modem,
+            bus,
+            last_call: None,
+            recorded: None,
+        }
+    }
+
+    /// Carries on from the last call seen before the bot stopped or the monitor crashed.
+    pub fn resume(modem: Modem, bus: Bus) -> Self {
+        CallMonitor {
+            last_call: store::load(CALL_STATE_FILE),
+            ..CallMonitor::new(modem, bus)
+        }
+    }
+
+    /// Whether the last call was recent enough that another may follow.
+    pub fn recently_called(&self, now: NaiveDateTime) -> bool {
+        self.last_call
+            .as_ref()
+            .is_some_and(|call| now - call.when < chrono::Duration::minutes(CALLS_BUSY))
+    }
+
+    pub async fn check(&mut self) -> Result<(), String> {
+        let html = self.modem.page(calls::PAGE).await;
+        if let Some(html) = &html {
+            check_layout(&self.bus, calls::PAGE, html, layout::check_calls(html));
+        }
+
+        let phone_calls = html.as_deref().and_then(calls::parse_calls);
+        if let Some(phone_calls) = &phone_calls {
+            let recorded = self.recorded.get_or_insert_with(history::calls);
+            history::record_calls(recorded, phone_calls);
+        }
+        let result = match (&html, &phone_calls) {
+            (None, _) => Err(format!("couldn't download {}", calls::PAGE)),
+            (Some(_), None) => Err(format!("couldn't read {}", calls::PAGE)),
+            (Some(_), Some(_)) => Ok(()),
+        };
+
+        let latest_calls =
+            phone_calls.and_then(|calls| calls::get_new_calls(&self.last_call, calls));
+
+        if let Some(mut latest_calls) = latest_calls {
+            debug!("There are new calls");
+
+            latest_calls.reverse();
+            for phone_call in &latest_calls {
+                self.bus.publish(Event::NewCall(phone_call.clone()));
+            }
+
+            if let Some(call) = Some(latest_calls.last().cloned()) {
+                self.last_call = call;
+
+                if store::save(CALL_STATE_FILE, &self.last_call).is_none() {
+                    warn!("Couldn't save the last call.");
+                }
+            }
+        } else {
+            debug!("No new calls")
+        }
+
+        result
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct LineState {
+    last_speed: LineSpeed,
+    last_ip: String,
+    // the newest entry of the modem's event log already used to explain a change
+    last_event: Option<NaiveDateTime>,
+}
+
+impl Default for LineState {
+    fn default() -> Self {
+        LineState {
+            last_speed: LineSpeed::Normal,
+            last_ip: String::new(),
+            last_event: None,
+        }
+    }
+}
+
+/// Publishes every reading of the line, and changes of line speed and IP address.
+pub struct SpeedMonitor {
+    modem: Modem,
+    bus: Bus,
+    state: LineState,
+}
+
+impl SpeedMonitor {
+    pub fn new(modem: Modem, bus: Bus) -> Self {
+        SpeedMonitor {
+            modem,
+            bus,
+            state: LineState::default(),
+        }
+    }
+
+    /// Carries on from the line as it was before the bot stopped or the monitor crashed.
+    pub fn resume(modem: Modem, bus: Bus) -> Self {
+        SpeedMonitor {
+            state: store::load(LINE_STATE_FILE),
+            ..SpeedMonitor::new(modem, bus)
+        }
+    }
+
+    /// Looks for what caused a change in the entries added to the event log since the last one.
+    async fn explain_change(&mut self) -> Option<String> {
+        let events = events::download_events(&self.modem).await?;
+        // entries from before the window are too old to explain a change, even if unused
+        let window = history::now() - chrono::Duration::minutes(EVENT_WINDOW);
+        let since = self
+            .state
+            .last_event
+            .map_or(window, |last_event| last_event.max(window));
+
+        let new: Vec<events::Event> = events
+            .into_iter()
+            .filter(|event| event.when > since)
+            .collect();
+
+        if let Some(newest) = new.iter().map(|event| event.when).max() {
+            self.state.last_event = Some(newest);
+        }
+
+        events::explain(&new)
+    }
+
+    pub fn is_degraded(&self) -> bool {
+        self.state.last_speed != LineSpeed::Normal
+    }
+
+    pub async fn check(&mut self) -> Result<(), String> {
+        let html = self.modem.page(stats::PAGE).await;
+        if let Some(html) = &html {
+            check_layout(&self.bus, stats::PAGE, html, layout::check_stats(html));
+        }
+
+        if let Some(stats) = html.as_deref().and_then(stats::parse_stats) {
+            history::append(&Record::Line {
+                at: history::now(),
+                stats: stats.clone(),
+            });
+
+            let different = stats.speed != self.state.last_speed || stats.ip != self.state.last_ip;
+            // the bot starting isn't a change worth explaining
+            let changed = different && !self.state.last_ip.is_empty();
+            let reason = if changed {
+                self.explain_change().await
+            } else {
+                None
+            };
+
+            self.bus.publish(Event::LineSample(stats.clone()));
+
+            if stats.speed != self.state.last_speed {
+                debug!("{}", stats.speed);
+                self.state.last_speed = stats.speed;
+
+                self.bus.publish(Event::SpeedStateChanged {
+                    stats: stats.clone(),
+                    reason: reason.clone(),
+                });
+            } else {
+                debug!("Skipping same speed state");
+            }
+
+            if stats.ip != self.state.last_ip {
+                debug!("{}", stats.ip);
+                self.state.last_ip = stats.ip.clone();
+
+                self.bus.publish(Event::IpChanged { stats, reason });
+            } else {
+                debug!("Skipping same ip");
+            }
+
+            if different && store::save(LINE_STATE_FILE, &self.state).is_none() {
+                warn!("Couldn't save the line state.");
+            }
+
+            Ok(())
+        } else if html.is_none() {
+            // the outage monitor tells the others
+            history::append(&Record::Unreachable { at: history::now() });
+
+            Err(format!("couldn't download {}", stats::PAGE))
+        } else {
+            Err(format!("couldn't read {}", stats::PAGE))
+        }
+    }
+}
+
+/// Follows outages of the modem and of the internet behind it, publishing when they
+/// start, until the chat has heard about it, and when they end.
+pub struct OutageMonitor {
+    modem: Modem,
+    bus: Bus,
+    pub host: String,
+}
+
+impl OutageMonitor {
+    pub fn new(modem: Modem, bus: Bus) -> Self {
+        OutageMonitor {
+            modem,
+            bus,
+            host: outage::host_from_env(),
+        }
+    }
+
+    pub async fn check(&mut self) {
+        let cause = outage::probe(&self.modem, &self.host).await;
+        let now = history::now();
+        let mut current = outage::current();
+
+        if current.as_ref().map(|outage| outage.cause) == cause {
+            if let Some(outage) = &current {
+                debug!("{}", outage.started_message());
+
+                // the first alert may not have got through
+                if !outage.alerted {
+                    self.bus.publish(Event::OutageStarted(outage.clone()));
+                }
+            }
+            return;
+        }
+
+        let ended = current.take().map(|mut ended| {
+            ended.to = Some(now);
+            info!("{} ended", ended.cause);
+            history::append(&Record::Outage(ended.clone()));
+            ended
+        });
+
+        if let Some(cause) = cause {
+            warn!("{} since {}", cause, now);
+            current = Some(Outage::new(cause, now));
+        }
+
+        // saved first, so the alert getting through can be noted on it
+        if outage::save_current(&current).is_none() {
+            warn!("Couldn't save the current outage.");
+        }
+
+        if let Some(ended) = ended {
+            self.bus.publish(Event::OutageEnded(ended));
+        }
+
+        if let Some(started) = current {
+            if started.cause == Cause::Modem {
+                self.bus.publish(Event::ModemUnreachable { at: now });
+            }
+            self.bus.publish(Event::OutageStarted(started));
+        }
+    }
+}
+
+/// Measures latency and loss to the probe targets, publishing when a target stays poor
+/// for as long as the rule says and when it recovers.
+pub struct ProbeMonitor {
+    bus: Bus,
+    pub prober: Prober,
+    pub rule: Rule,
+    samples: BTreeMap<String, Vec<(NaiveDateTime, ProbeResult)>>,
+    degraded: BTreeSet<String>,
+}
+
+impl ProbeMonitor {
+    pub fn new(modem: &Modem, bus: Bus) -> Self {
+        ProbeMonitor {
+            bus,
+            prober: Prober::from_env(modem),
+            rule: Rule::from_env(),
+            samples: BTreeMap::new(),
+            degraded: BTreeSet::new(),
+        }
+    }
+
+    pub async fn check(&mut self) {
+        let results = self.prober.run().await;
+        let now = history::now();
+
+        history::append(&Record::Probe {
+            at: now,
+            results: results.clone(),
+        });
+
+        for result in results {
+            let samples = self.samples.entry(result.target.clone()).or_default();
+            samples.push((now, result.clone()));
+            // the rule only looks back as far as it lasts
+            samples.retain(|(at, _)| now - *at <= self.rule.lasting * 2);
+
+            if let Some(since) = self.rule.degraded_since(samples, now) {
+                if !self.degraded.insert(result.target.clone()) {
+                    debug!("Skipping same poor connection to {}", result.target);
+                    continue;
+                }
+
+                self.bus
+                    .publish(Event::ConnectionDegraded { result, since });
+            } else if !self.rule.is_bad(&result) && self.degraded.remove(&result.target) {
+                self.bus.publish(Event::ConnectionRecovered(result));
+            }
+        }
+    }
+}
+
+/// Publishes devices joining the network for the first time, unless they are trusted.
+pub struct DeviceMonitor {
+    modem: Modem,
+    bus: Bus,
+}
+
+impl DeviceMonitor {
+    pub fn new(modem: Modem, bus: Bus) -> Self {
+        DeviceMonitor { modem, bus }
+    }
+
+    pub async fn check(&mut self) -> Result<(), String> {
+        let devices = match devices::download_devices(&self.modem).await {
+            Some(devices) => devices,
+            None => return Err(format!("couldn't get the devices from {}", devices::PAGE)),
+        };
+
+        let mut trusted = TrustedDevices::load();
+        // the devices already there when the bot first looks aren't news
+        let learning = trusted.is_learning();
+        let new_devices = trusted.see(&devices);
+
+        if new_devices.is_empty() && !learning {
+            debug!("No new devices");
+            return Ok(());
+        }
+
+        if trusted.save().is_none() {
+            warn!("Couldn't save the devices seen.");
+        }
+
+        if learning {
+            info!("Learnt {} devices", new_devices.len());
+            return Ok(());
+        }
+
+        for device in new_devices {
+            self.bus.publish(Event::NewDevice(device.clone()));
+        }
+
+        Ok(())
+    }
+}
+
+/// Watches the registration of the modem's phone lines, as calls can't arrive on a line
+/// the SIP server has forgotten and `CallMonitor` would just see nothing.
+pub struct VoipMonitor {
+    modem: Modem,
+    bus: Bus,
+    // whether each line was registered, by name
+    registered: BTreeMap<String, bool>,
+}
+
+impl VoipMonitor {
+    pub fn new(modem: Modem, bus: Bus) -> Self {
+        VoipMonitor {
+            modem,
+            bus,
+            registered: BTreeMap::new(),
+        }
+    }
+
+    pub async fn check(&mut self) -> Result<(), String> {
+        let lines = match voip::download_lines(&self.modem).await {
+            Some(lines) => lines,
+            None => return Err(format!("couldn't get the phone lines from {}", voip::PAGE)),
+        };
+
+        for line in lines.iter().filter(|line| line.is_configured()) {
+            // a line is taken as registered until seen otherwise, so one down at start is news
+            let was_registered = self.registered.get(&line.name).copied().unwrap_or(true);
+
+            if line.is_registered() == was_registered {
+                debug!("Skipping same registration for {}", line.name);
+                continue;
+            }
+
+            self.bus.publish(Event::VoipRegistration {
+                line: line.label(),
+                registered: line.is_registered(),
+            });
+            self.registered
+                .insert(line.name.clone(), line.is_registered());
+        }
+
+        Ok(())
+    }
+}
+
+/// Undoes what was asked for a while only: switches Wi-Fi networks back after `/wifi` or
+/// `/guestwifi`, and closes the ports opened with `/openport` once they expire.
+pub struct TimerMonitor {
+    modem: Modem,
+    bus: Bus,
+}
+
+impl TimerMonitor {
+    pub fn new(modem: Modem, bus: Bus) -> Self {
+        TimerMonitor { modem, bus }
+    }
+
+    pub async fn check(&mut self) {
+        let now = history::now();
+        let mut timers = Timers::load();
+        let due_wifi = timers.take_due_wifi(now);
+        let due_ports = timers.take_due_ports(now);
+
+        if due_wifi.is_empty() && due_ports.is_empty() {
+            return;
+        }
+
+        let mut done = Vec::new();
+
+        for (network, enabled, at) in due_wifi {
+            if wifi::set_enabled(&self.modem, network, enabled)
+                .await
+                .is_some()
+            {
+                done.push(Event::WifiSwitched { network, enabled });
+            } else {
+                // try again on the next check
+                warn!("Couldn't switch {}", network);
+                timers.set_wifi(network, enabled, at);
+            }
+        }
+
+        for (external, expires) in due_ports {
+            if nat::remove_forward(&self.modem, external).await.is_some() {
+                done.push(Event::PortClosed { external });
+            } else {
+                warn!("Couldn't close port {}", external);
+                timers.set_port(external, expires);
+            }
+        }
+
+        if timers.save().is_none() {
+            warn!("Couldn't save the timers.");
+        }
+
+        for event in done {
+            self.bus.publish(event);
+        }
+    }
+}
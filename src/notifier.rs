@@ -0,0 +1,337 @@
+use crate::actions;
+use crate::bus::{self, Bus};
+use crate::contacts::Contacts;
+use crate::escalation::Escalation;
+use crate::event::Event;
+use crate::history;
+use crate::monitor::{self, call_message};
+use crate::notify;
+use crate::outage;
+use crate::quiet::Priority;
+use crate::rules::{Context, RuleSet};
+use crate::spam::{Blocklist, SpamFilter, SpamMode, SpamReason};
+use crate::timm::calls::PhoneCall;
+use crate::timm::modem::Modem;
+use std::path::Path;
+use teloxide::prelude::*;
+use teloxide::types::InputFile;
+use tokio::sync::broadcast::Receiver;
+
+/// Subscribes to the bus and tells the chat about what the monitors noticed: spam, urgent
+/// calls and the alert rules for calls, changes of the line, outages and the rest.
+pub struct Notifier {
+    modem: Modem,
+    bus: Bus,
+    spam_filter: SpamFilter,
+    pub escalation: Escalation,
+    pub rules: RuleSet,
+}
+
+impl Notifier {
+    pub fn new(modem: Modem, bus: Bus) -> Self {
+        Notifier {
+            modem,
+            bus,
+            spam_filter: SpamFilter::from_env(),
+            escalation: Escalation::from_env(),
+            rules: RuleSet::from_env(),
+        }
+    }
+
+    /// Handles events until the bus is gone.
+    pub async fn run(&self, bot: &Bot, chat_id: ChatId, receiver: &mut Receiver<Event>) {
+        while let Some(event) = bus::next(receiver).await {
+            self.handle(bot, chat_id, &event).await;
+        }
+    }
+
+    /// Handles the events already published, without waiting for more.
+    pub async fn drain(&self, bot: &Bot, chat_id: ChatId, receiver: &mut Receiver<Event>) {
+        while let Some(event) = bus::try_next(receiver) {
+            self.handle(bot, chat_id, &event).await;
+        }
+    }
+
+    pub async fn handle(&self, bot: &Bot, chat_id: ChatId, event: &Event) {
+        match event {
+            Event::NewCall(phone_call) => self.announce_call(bot, chat_id, phone_call).await,
+            Event::LayoutChanged {
+                page,
+                mismatch,
+                copy,
+            } => {
+                self.announce_layout(bot, chat_id, page, mismatch, copy.as_deref())
+                    .await
+            }
+            // told again on the next check until it gets through
+            Event::OutageStarted(outage) => {
+                if self.announce(bot, chat_id, event, message(event)).await
+                    && outage::set_alerted(outage).is_none()
+                {
+                    warn!("Couldn't save the current outage.");
+                }
+            }
+            _ => {
+                self.announce(bot, chat_id, event, message(event)).await;
+            }
+        }
+    }
+
+    async fn announce_call(&self, bot: &Bot, chat_id: ChatId, phone_call: &PhoneCall) {
+        debug!("{}", phone_call);
+
+        let phone_calls = history::calls();
+        let contacts = Contacts::load();
+        let blocklist = Blocklist::load();
+
+        let mut text = call_message(&contacts, phone_call);
+        let reason = self
+            .spam_filter
+            .classify(&blocklist, &contacts, phone_call, &phone_calls);
+        let keyboard = phone_call.who.number().and_then(actions::call_keyboard);
+
+        // a blocked caller is never announced, not even by a rule
+        if reason == Some(SpamReason::Blocked) {
+            info!("Not announcing blocked call from {}", phone_call.who);
+            return;
+        }
+
+        // the alert rules, when one matches, decide instead of what follows, suspected spam
+        // included: it reaches them tagged with the reason, whatever the spam mode
+        let tagged = match &reason {
+            Some(reason) => format!("{}\n{}", text, reason),
+            None => text.clone(),
+        };
+        let context = Context {
+            contacts: &contacts,
+            phone_calls: &phone_calls,
+            now: history::now(),
+        };
+        if self
+            .rules
+            .apply(
+                bot,
+                chat_id,
+                &self.modem,
+                &self.bus,
+                &Event::NewCall(phone_call.clone()),
+                &context,
+                &tagged,
+                keyboard.clone(),
+            )
+            .await
+        {
+            return;
+        }
+
+        // calls close together are urgent rather than spam, unless the number is known spam
+        if let Some(count) = self
+            .escalation
+            .check(phone_call, &phone_calls)
+            .filter(|_| reason.as_ref().is_none_or(SpamReason::is_repeat))
+        {
+            self.escalate(bot, chat_id, &text, count, phone_call).await;
+            return;
+        }
+
+        if let Some(reason) = reason {
+            if self.spam_filter.mode == SpamMode::Silent {
+                info!(
+                    "Not announcing spam call from {}: {}",
+                    phone_call.who, reason
+                );
+                return;
+            }
+
+            text = format!("{}\n{}", text, reason);
+        }
+
+        let priority = if contacts.is_vip(&phone_call.who.key()) {
+            Priority::High
+        } else {
+            Priority::Normal
+        };
+
+        if notify::alert(bot, chat_id, text, keyboard, priority)
+            .await
+            .is_none()
+        {
+            warn!("Couldn't send monitor_calls message.");
+        }
+    }
+
+    async fn escalate(
+        &self,
+        bot: &Bot,
+        chat_id: ChatId,
+        text: &str,
+        count: usize,
+        phone_call: &PhoneCall,
+    ) {
+        info!("Escalating {} calls from {}", count, phone_call.who);
+
+        let text = self.escalation.message(text, count);
+        let keyboard = phone_call.who.number().and_then(actions::call_keyboard);
+
+        if notify::alert(bot, chat_id, text.clone(), keyboard, Priority::High)
+            .await
+            .is_none()
+        {
+            warn!("Couldn't send monitor_calls escalation.");
+        }
+
+        // the call buttons only answer the main chat, so the others get the text alone
+        for other in &self.escalation.chats {
+            if notify::alert(bot, *other, text.clone(), None, Priority::High)
+                .await
+                .is_none()
+            {
+                warn!("Couldn't send monitor_calls escalation to {}.", other);
+            }
+        }
+    }
+
+    /// Runs the alert rules for any other event, or sends `text` when none match.
+    /// Whether a rule or the message took care of it.
+    async fn announce(
+        &self,
+        bot: &Bot,
+        chat_id: ChatId,
+        event: &Event,
+        text: Option<String>,
+    ) -> bool {
+        let contacts = Contacts::load();
+        let context = Context {
+            contacts: &contacts,
+            phone_calls: &[],
+            now: history::now(),
+        };
+        let fallback = match event {
+            Event::ModemUnreachable { .. } => "📵 The modem can't be reached.".to_string(),
+            Event::RebootIssued { by, .. } => format!("🔄 The modem is rebooting ({}).", by),
+            _ => event
+                .stats()
+                .map(|stats| stats.to_string())
+                .unwrap_or_default(),
+        };
+
+        if self
+            .rules
+            .apply(
+                bot,
+                chat_id,
+                &self.modem,
+                &self.bus,
+                event,
+                &context,
+                text.as_deref().unwrap_or(&fallback),
+                None,
+            )
+            .await
+        {
+            return true;
+        }
+
+        let text = match text {
+            Some(text) => text,
+            None => return true,
+        };
+
+        // calls can't come through, which is worth waking up for
+        let priority = match event {
+            Event::VoipRegistration {
+                registered: false, ..
+            } => Priority::High,
+            _ => Priority::Normal,
+        };
+
+        if notify::alert(bot, chat_id, text, None, priority)
+            .await
+            .is_none()
+        {
+            warn!("Couldn't send the {:?} message.", event.kind());
+            return false;
+        }
+
+        true
+    }
+
+    /// Sends a layout change to the admin with the copy of the page, outside of the rules
+    /// and quiet hours, as it's about the bot rather than the line.
+    async fn announce_layout(
+        &self,
+        bot: &Bot,
+        chat_id: ChatId,
+        page: &str,
+        mismatch: &str,
+        copy: Option<&Path>,
+    ) {
+        let text = format!(
+            "🧩 The modem's {} page changed and can't be read: {}.",
+            page, mismatch
+        );
+        let sent = match copy {
+            Some(path) => bot
+                .send_document(chat_id, InputFile::file(path))
+                .caption(text)
+                .await
+                .is_ok(),
+            None => bot.send_message(chat_id, text).await.is_ok(),
+        };
+
+        if !sent {
+            warn!("Couldn't send layout change message.");
+        } else if monitor::set_layout_reported(page, mismatch).is_none() {
+            warn!("Couldn't save the reported layout changes.");
+        }
+    }
+}
+
+/// What the chat is told about an event, or nothing unless a rule asks.
+fn message(event: &Event) -> Option<String> {
+    let text = match event {
+        Event::SpeedStateChanged { stats, reason } => match reason {
+            Some(reason) => format!("{} — {}", stats.speed, reason),
+            None => format!("{}", stats.speed),
+        },
+        Event::IpChanged { stats, reason } => match reason {
+            Some(reason) => format!("IP changed to {} — {}", stats.ip, reason),
+            None => format!("IP is {}", stats.ip),
+        },
+        Event::OutageStarted(outage) => outage.started_message(),
+        Event::OutageEnded(outage) => outage.ended_message(history::now()),
+        Event::ConnectionDegraded { result, since } => format!(
+            "⚠️ Poor connection since {}: {}",
+            since.format("%H:%M"),
+            result
+        ),
+        Event::ConnectionRecovered(result) => {
+            format!("✅ Connection back to normal: {}", result)
+        }
+        Event::NewDevice(device) => format!(
+            "🆕 New device on the network:\n{}\nLabel it with /trust {} <name>",
+            device, device.mac
+        ),
+        Event::VoipRegistration {
+            line,
+            registered: true,
+        } => format!("✅ {} registered again.", line),
+        Event::VoipRegistration {
+            line,
+            registered: false,
+        } => format!(
+            "☎️ {} is no longer registered: calls won't come through.",
+            line
+        ),
+        Event::WifiSwitched { network, enabled } => format!(
+            "{} {} switched {} as planned.",
+            if *enabled { "📶" } else { "📴" },
+            network,
+            if *enabled { "on" } else { "off" }
+        ),
+        Event::PortClosed { external } => format!("🔒 Port {} closed as planned.", external),
+        _ => return None,
+    };
+
+    Some(text)
+}
@@ -0,0 +1,61 @@
+use crate::history;
+use crate::quiet::{self, Delivery, Dnd, Held, Priority, QuietHours};
+use teloxide::{prelude::*, types::InlineKeyboardMarkup};
+
+/// Sends a monitor alert, silently or held back for later if the chat is in quiet hours.
+pub async fn alert(
+    bot: &Bot,
+    chat_id: ChatId,
+    text: String,
+    keyboard: Option<InlineKeyboardMarkup>,
+    priority: Priority,
+) -> Option<()> {
+    let now = history::now();
+    let delivery = QuietHours::from_env().delivery(&Dnd::load(), chat_id.0, now, priority);
+
+    debug!("Delivering alert to {} as {:?}", chat_id, delivery);
+
+    if delivery == Delivery::Hold {
+        let mut held = Held::load();
+        held.push(chat_id.0, text);
+
+        return held.save();
+    }
+
+    let mut message = bot
+        .send_message(chat_id, text)
+        .disable_notification(delivery == Delivery::Silent);
+
+    if let Some(keyboard) = keyboard {
+        message = message.reply_markup(keyboard);
+    }
+
+    message.await.ok().map(|_| ())
+}
+
+/// Sends the alerts held back for every chat that is no longer quiet.
+pub async fn release_held(bot: &Bot) {
+    let quiet_hours = QuietHours::from_env();
+    let dnd = Dnd::load();
+    let mut held = Held::load();
+    let now = history::now();
+    let mut released = false;
+
+    for chat in held.chats() {
+        if quiet_hours.delivery(&dnd, chat, now, Priority::Normal) != Delivery::Loud {
+            continue;
+        }
+
+        for digest in quiet::digest(&held.take(chat)) {
+            if bot.send_message(ChatId(chat), digest).await.is_err() {
+                warn!("Couldn't send held alerts to {}.", chat);
+            }
+        }
+
+        released = true;
+    }
+
+    if released && held.save().is_none() {
+        warn!("Couldn't save held alerts.");
+    }
+}
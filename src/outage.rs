@@ -0,0 +1,231 @@
+use crate::history::{self, Record};
+use crate::schedule;
+use crate::store;
+use crate::timm::modem::Modem;
+use crate::timm::stats;
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::net::Ipv4Addr;
+use tokio::net::lookup_host;
+use tokio::time::timeout;
+
+// the outage still going on, kept across restarts
+const FILE: &str = "outage.json";
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Cause {
+    /// The modem doesn't answer on the LAN.
+    Modem,
+    /// The modem answers, but has no public IP or names don't resolve.
+    Internet,
+}
+
+impl Display for Cause {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Cause::Modem => "📡 Modem unreachable",
+                Cause::Internet => "🌐 Internet down",
+            }
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct Outage {
+    pub cause: Cause,
+    pub from: NaiveDateTime,
+    pub to: Option<NaiveDateTime>,
+    /// Whether the chat already heard about it while it was going on.
+    #[serde(default)]
+    pub alerted: bool,
+}
+
+impl Outage {
+    pub fn new(cause: Cause, from: NaiveDateTime) -> Self {
+        Outage {
+            cause,
+            from,
+            to: None,
+            alerted: false,
+        }
+    }
+
+    pub fn lasted(&self, now: NaiveDateTime) -> Duration {
+        self.to.unwrap_or(now) - self.from
+    }
+
+    /// "🌐 Internet down since 14:02"
+    pub fn started_message(&self) -> String {
+        format!("{} since {}", self.cause, self.from.format("%H:%M"))
+    }
+
+    /// "✅ Back after 17m", preceded by when it started if nobody was told.
+    pub fn ended_message(&self, now: NaiveDateTime) -> String {
+        let back = format!(
+            "✅ Back after {}",
+            schedule::format_duration(self.lasted(now))
+        );
+
+        if self.alerted {
+            back
+        } else {
+            format!("{}\n{}", self.started_message(), back)
+        }
+    }
+}
+
+impl Display for Outage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.to {
+            Some(to) => write!(
+                f,
+                "{} {} for {}",
+                self.cause,
+                self.from.format("%-d %b %H:%M"),
+                schedule::format_duration(to - self.from)
+            ),
+            None => write!(
+                f,
+                "{} since {}, still going on",
+                self.cause,
+                self.from.format("%-d %b %H:%M")
+            ),
+        }
+    }
+}
+
+/// The outage going on now, if any.
+pub fn current() -> Option<Outage> {
+    store::load(FILE)
+}
+
+pub fn save_current(outage: &Option<Outage>) -> Option<()> {
+    store::save(FILE, outage)
+}
+
+/// Notes that the chat heard about `outage` starting, unless it has ended since.
+pub fn set_alerted(outage: &Outage) -> Option<()> {
+    let mut current = current();
+
+    match &mut current {
+        Some(current) if current.cause == outage.cause && current.from == outage.from => {
+            current.alerted = true;
+        }
+        _ => return Some(()),
+    }
+
+    save_current(&current)
+}
+
+/// The outages that ended since `since`, oldest first.
+pub fn load_since(since: NaiveDateTime) -> Vec<Outage> {
+    history::load_since(since)
+        .into_iter()
+        .filter_map(|record| match record {
+            Record::Outage(outage) => Some(outage),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Works out whether the modem and the internet behind it are reachable.
+pub async fn probe(modem: &Modem, host: &str) -> Option<Cause> {
+    let html = match modem.page(stats::PAGE).await {
+        Some(html) => html,
+        None => return Some(Cause::Modem),
+    };
+
+    // a page the parsers can't read says nothing either way about the IP
+    let has_ip = stats::parse_stats(&html).is_none_or(|stats| {
+        stats
+            .ip
+            .parse::<Ipv4Addr>()
+            .is_ok_and(|ip| !ip.is_unspecified())
+    });
+
+    if !has_ip {
+        debug!("The modem has no public IP");
+        return Some(Cause::Internet);
+    }
+
+    let resolved = timeout(modem.timeout, lookup_host((host, 443)))
+        .await
+        .ok()
+        .and_then(|addrs| addrs.ok())
+        .is_some_and(|mut addrs| addrs.next().is_some());
+
+    if resolved {
+        None
+    } else {
+        debug!("Couldn't resolve {}", host);
+        Some(Cause::Internet)
+    }
+}
+
+/// The host whose name must resolve for the internet to count as up, `CONNECTIVITY_HOST`
+/// or Telegram's by default, as there is no point in alerting without it.
+pub fn host_from_env() -> String {
+    env::var("CONNECTIVITY_HOST").unwrap_or_else(|_| "api.telegram.org".to_string())
+}
+
+pub fn list_message(outages: &[Outage], current: Option<&Outage>) -> String {
+    if outages.is_empty() && current.is_none() {
+        return "No outages recorded.".to_string();
+    }
+
+    let mut lines: Vec<String> = outages.iter().map(|outage| outage.to_string()).collect();
+
+    if let Some(current) = current {
+        lines.push(current.to_string());
+    }
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 5)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_outage_messages() {
+        let mut outage = Outage::new(Cause::Internet, at(14, 2));
+
+        assert_eq!(outage.started_message(), "🌐 Internet down since 14:02");
+        assert_eq!(
+            outage.ended_message(at(14, 19)),
+            "🌐 Internet down since 14:02\n✅ Back after 17m"
+        );
+
+        outage.alerted = true;
+        assert_eq!(outage.ended_message(at(14, 19)), "✅ Back after 17m");
+    }
+
+    #[test]
+    fn test_list_message() {
+        let ended = Outage {
+            to: Some(at(9, 30)),
+            ..Outage::new(Cause::Modem, at(9, 0))
+        };
+        let current = Outage::new(Cause::Internet, at(14, 2));
+
+        assert_eq!(list_message(&[], None), "No outages recorded.");
+        assert_eq!(
+            list_message(&[ended], Some(&current)),
+            "📡 Modem unreachable 5 Oct 09:00 for 30m\n🌐 Internet down since 5 Oct 14:02, still going on"
+        );
+    }
+}
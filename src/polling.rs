@@ -0,0 +1,209 @@
+use std::collections::hash_map::RandomState;
+use std::env;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::time::{sleep, Duration};
+
+// the longest wait while the modem doesn't answer
+const MAX_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// as a fraction of each wait
+const DEFAULT_JITTER: f64 = 0.1;
+
+/// How long a monitor waits before polling the modem again: less while something is going on,
+/// more after each failure in a row, and never exactly the same so the monitors spread out.
+#[derive(PartialEq, Debug, Clone)]
+pub struct Pacing {
+    pub normal: Duration,
+    /// While something is going on, such as a call just now.
+    pub busy: Duration,
+    /// How much each wait varies either way, as a fraction of it.
+    pub jitter: f64,
+}
+
+impl Pacing {
+    /// Reads the jitter from `POLL_JITTER`, a percentage such as "10%".
+    pub fn from_env(normal: Duration, busy: Duration) -> Self {
+        let jitter = match env::var("POLL_JITTER") {
+            Ok(jitter) => parse_jitter(&jitter).unwrap_or_else(|| {
+                warn!("Couldn't parse POLL_JITTER {}", jitter);
+                DEFAULT_JITTER
+            }),
+            Err(_) => DEFAULT_JITTER,
+        };
+
+        Pacing {
+            normal,
+            busy,
+            jitter,
+        }
+    }
+
+    /// The wait before any jitter, `failures` being the checks in a row that failed.
+    pub fn interval(&self, busy: bool, failures: u32) -> Duration {
+        if failures > 0 {
+            return self
+                .normal
+                .saturating_mul(2u32.saturating_pow(failures))
+                .min(MAX_INTERVAL.max(self.normal));
+        }
+
+        if busy {
+            self.busy
+        } else {
+            self.normal
+        }
+    }
+
+    /// Spreads `interval` by the jitter, `random` being between 0 and 1.
+    pub fn jittered(&self, interval: Duration, random: f64) -> Duration {
+        interval.mul_f64(1.0 + self.jitter * (2.0 * random - 1.0))
+    }
+
+    pub fn next(&self, busy: bool, failures: u32) -> Duration {
+        self.jittered(self.interval(busy, failures), random())
+    }
+}
+
+/// Reads "10%" or "10" as a tenth, refusing anything from 100%.
+fn parse_jitter(value: &str) -> Option<f64> {
+    let percent: f64 = value.trim().trim_end_matches('%').trim().parse().ok()?;
+
+    (0.0..100.0).contains(&percent).then_some(percent / 100.0)
+}
+
+// good enough to spread the polls, without another dependency
+fn random() -> f64 {
+    RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64
+}
+
+/// Lets `/poll` wake the monitors, so they poll the modem at once. Each clone keeps the
+/// fires it hasn't waited for yet, so a monitor busy checking polls again once it's done.
+#[derive(Debug)]
+pub struct Trigger {
+    sender: Arc<watch::Sender<()>>,
+    receiver: watch::Receiver<()>,
+}
+
+impl Default for Trigger {
+    fn default() -> Self {
+        let (sender, receiver) = watch::channel(());
+
+        Trigger {
+            sender: Arc::new(sender),
+            receiver,
+        }
+    }
+}
+
+// a clone only hears the fires that come after it
+impl Clone for Trigger {
+    fn clone(&self) -> Self {
+        Trigger {
+            sender: self.sender.clone(),
+            receiver: self.sender.subscribe(),
+        }
+    }
+}
+
+impl Trigger {
+    pub fn fire(&self) {
+        self.sender.send_replace(());
+    }
+
+    /// Waits for `duration`, or until the trigger is fired, at once if it was fired since
+    /// the last wait.
+    pub async fn wait(&mut self, duration: Duration) {
+        tokio::select! {
+            _ = sleep(duration) => {},
+            _ = self.receiver.changed() => info!("Polling at once, as asked"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calls() -> Pacing {
+        Pacing {
+            normal: Duration::from_secs(60),
+            busy: Duration::from_secs(15),
+            jitter: 0.1,
+        }
+    }
+
+    #[test]
+    fn test_interval() {
+        let pacing = calls();
+
+        assert_eq!(pacing.interval(false, 0), Duration::from_secs(60));
+        assert_eq!(pacing.interval(true, 0), Duration::from_secs(15));
+        // an unreachable modem isn't busy
+        assert_eq!(pacing.interval(true, 1), Duration::from_secs(120));
+        assert_eq!(pacing.interval(false, 3), Duration::from_secs(480));
+        assert_eq!(pacing.interval(false, 4), MAX_INTERVAL);
+        assert_eq!(pacing.interval(false, 40), MAX_INTERVAL);
+    }
+
+    #[test]
+    fn test_jittered() {
+        let pacing = calls();
+        let interval = Duration::from_secs(60);
+
+        assert_eq!(pacing.jittered(interval, 0.0), Duration::from_secs(54));
+        assert_eq!(pacing.jittered(interval, 0.5), interval);
+        assert_eq!(pacing.jittered(interval, 1.0), Duration::from_secs(66));
+
+        let next = pacing.next(false, 0);
+        assert!(next >= Duration::from_secs(54) && next <= Duration::from_secs(66));
+    }
+
+    #[test]
+    fn test_parse_jitter() {
+        assert_eq!(parse_jitter("25%"), Some(0.25));
+        assert_eq!(parse_jitter("0"), Some(0.0));
+        assert_eq!(parse_jitter("100%"), None);
+        assert_eq!(parse_jitter("lots"), None);
+    }
+
+    #[tokio::test]
+    async fn test_trigger() {
+        let trigger = Trigger::default();
+        let mut waiting = trigger.clone();
+        let waiter = tokio::spawn(async move { waiting.wait(Duration::from_secs(600)).await });
+
+        // let the monitor start waiting
+        sleep(Duration::from_millis(50)).await;
+        trigger.fire();
+
+        assert!(tokio::time::timeout(Duration::from_secs(1), waiter)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_trigger_fired_while_busy() {
+        let trigger = Trigger::default();
+        let mut waiting = trigger.clone();
+
+        // fired while the monitor is still checking, twice
+        trigger.fire();
+        trigger.fire();
+
+        assert!(tokio::time::timeout(
+            Duration::from_secs(1),
+            waiting.wait(Duration::from_secs(600))
+        )
+        .await
+        .is_ok());
+        // both fires were for the same poll
+        assert!(tokio::time::timeout(
+            Duration::from_millis(50),
+            waiting.wait(Duration::from_secs(600))
+        )
+        .await
+        .is_err());
+    }
+}
@@ -0,0 +1,313 @@
+use crate::schedule;
+use crate::timm::modem::Modem;
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::time::Instant;
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+
+/// How a target is reached.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Method {
+    /// Connects to a `host:port`.
+    Tcp(String),
+    /// Gets a URL and waits for the response headers.
+    Http(String),
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Target {
+    pub name: String,
+    pub method: Method,
+}
+
+impl TryFrom<&str> for Target {
+    type Error = ();
+
+    /// Reads targets such as "dns=tcp:1.1.1.1:53" or "web=https://example.com/".
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (name, address) = value.trim().split_once('=').ok_or(())?;
+        let address = address.trim();
+
+        let method = if let Some(address) = address.strip_prefix("tcp:") {
+            Method::Tcp(address.to_string())
+        } else if address.starts_with("http://") || address.starts_with("https://") {
+            Method::Http(address.to_string())
+        } else {
+            return Err(());
+        };
+
+        Ok(Target {
+            name: name.trim().to_string(),
+            method,
+        })
+    }
+}
+
+/// How one target answered a round of attempts.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub target: String,
+    /// The average of the attempts that got an answer, in milliseconds.
+    pub latency: Option<u32>,
+    /// The share of attempts that got no answer, in percent.
+    pub loss: u32,
+}
+
+impl Display for ProbeResult {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.latency {
+            Some(latency) => write!(f, "{} {}ms, {}% loss", self.target, latency, self.loss),
+            None => write!(f, "{} unreachable", self.target),
+        }
+    }
+}
+
+/// Measures latency and loss to the targets in `PROBE_TARGETS`.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Prober {
+    pub targets: Vec<Target>,
+    pub attempts: u32,
+    pub timeout: std::time::Duration,
+}
+
+impl Prober {
+    /// Reads `PROBE_TARGETS`, such as "dns=tcp:1.1.1.1:53, web=https://example.com/",
+    /// by default the modem and a public DNS server, and `PROBE_ATTEMPTS`, 5 by default.
+    pub fn from_env(modem: &Modem) -> Self {
+        let targets = match env::var("PROBE_TARGETS") {
+            Ok(targets) => parse_targets(&targets),
+            Err(_) => default_targets(modem),
+        };
+
+        let attempts = env::var("PROBE_ATTEMPTS")
+            .ok()
+            .and_then(|attempts| attempts.parse().ok())
+            .filter(|attempts| *attempts > 0)
+            .unwrap_or(5);
+
+        Prober {
+            targets,
+            attempts,
+            timeout: std::time::Duration::from_secs(2),
+        }
+    }
+
+    pub async fn run(&self) -> Vec<ProbeResult> {
+        let mut results = Vec::new();
+
+        for target in &self.targets {
+            let mut answered = Vec::new();
+
+            for _ in 0..self.attempts {
+                if let Some(latency) = attempt(&target.method, self.timeout).await {
+                    answered.push(latency.as_millis() as u32);
+                }
+            }
+
+            let latency = (!answered.is_empty())
+                .then(|| answered.iter().sum::<u32>() / answered.len() as u32);
+            let loss = (self.attempts - answered.len() as u32) * 100 / self.attempts;
+
+            debug!("Probed {}: {:?}ms, {}% loss", target.name, latency, loss);
+
+            results.push(ProbeResult {
+                target: target.name.clone(),
+                latency,
+                loss,
+            });
+        }
+
+        results
+    }
+}
+
+async fn attempt(method: &Method, limit: std::time::Duration) -> Option<std::time::Duration> {
+    let start = Instant::now();
+
+    match method {
+        Method::Tcp(address) => {
+            timeout(limit, TcpStream::connect(address.as_str()))
+                .await
+                .ok()?
+                .ok()?;
+        }
+        Method::Http(url) => {
+            reqwest::Client::builder()
+                .timeout(limit)
+                .build()
+                .ok()?
+                .get(url)
+                .send()
+                .await
+                .ok()?;
+        }
+    }
+
+    Some(start.elapsed())
+}
+
+pub fn parse_targets(value: &str) -> Vec<Target> {
+    value
+        .split(',')
+        .filter(|target| !target.trim().is_empty())
+        .filter_map(|target| match Target::try_from(target) {
+            Ok(target) => Some(target),
+            Err(_) => {
+                warn!("Couldn't parse probe target {}", target);
+                None
+            }
+        })
+        .collect()
+}
+
+fn default_targets(modem: &Modem) -> Vec<Target> {
+    let mut targets = Vec::new();
+
+    if let Ok(url) = reqwest::Url::parse(&modem.url) {
+        if let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) {
+            targets.push(Target {
+                name: "gateway".to_string(),
+                method: Method::Tcp(format!("{}:{}", host, port)),
+            });
+        }
+    }
+
+    targets.push(Target {
+        name: "dns".to_string(),
+        method: Method::Tcp("1.1.1.1:53".to_string()),
+    });
+
+    targets
+}
+
+/// When a target counts as degraded, such as latency over 200ms or loss over 20% for 10 minutes.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Rule {
+    pub latency: u32,
+    pub loss: u32,
+    pub lasting: Duration,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            latency: 200,
+            loss: 20,
+            lasting: Duration::minutes(10),
+        }
+    }
+}
+
+impl Rule {
+    /// Reads `PROBE_MAX_LATENCY` in ms, `PROBE_MAX_LOSS` in percent and `PROBE_LASTING`, such as "10m".
+    pub fn from_env() -> Self {
+        let default = Rule::default();
+        let number = |name: &str| env::var(name).ok().and_then(|value| value.parse().ok());
+
+        Rule {
+            latency: number("PROBE_MAX_LATENCY").unwrap_or(default.latency),
+            loss: number("PROBE_MAX_LOSS").unwrap_or(default.loss),
+            lasting: env::var("PROBE_LASTING")
+                .ok()
+                .and_then(|lasting| schedule::parse_duration(&lasting))
+                .unwrap_or(default.lasting),
+        }
+    }
+
+    pub fn is_bad(&self, result: &ProbeResult) -> bool {
+        result.loss > self.loss || result.latency.is_none_or(|latency| latency > self.latency)
+    }
+
+    /// Since when every sample of the target has been bad, once that has lasted as long as
+    /// the rule says. The samples are the target's results, oldest first.
+    pub fn degraded_since(
+        &self,
+        samples: &[(NaiveDateTime, ProbeResult)],
+        now: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        samples
+            .iter()
+            .rev()
+            .take_while(|(_, result)| self.is_bad(result))
+            .last()
+            .map(|(at, _)| *at)
+            .filter(|since| now - *since >= self.lasting)
+    }
+
+    pub fn is_degraded(
+        &self,
+        samples: &[(NaiveDateTime, ProbeResult)],
+        now: NaiveDateTime,
+    ) -> bool {
+        self.degraded_since(samples, now).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 5)
+            .unwrap()
+            .and_hms_opt(14, minute, 0)
+            .unwrap()
+    }
+
+    fn sample(minute: u32, latency: Option<u32>, loss: u32) -> (NaiveDateTime, ProbeResult) {
+        (
+            at(minute),
+            ProbeResult {
+                target: "gateway".to_string(),
+                latency,
+                loss,
+            },
+        )
+    }
+
+    #[test]
+    fn test_parse_targets() {
+        assert_eq!(
+            parse_targets("dns=tcp:1.1.1.1:53, web=https://example.com/, bad=ping:1.1.1.1"),
+            vec![
+                Target {
+                    name: "dns".to_string(),
+                    method: Method::Tcp("1.1.1.1:53".to_string()),
+                },
+                Target {
+                    name: "web".to_string(),
+                    method: Method::Http("https://example.com/".to_string()),
+                },
+            ]
+        );
+        assert_eq!(
+            default_targets(&Modem::default())[0].method,
+            Method::Tcp("192.168.1.1:80".to_string())
+        );
+    }
+
+    #[test]
+    fn test_degraded() {
+        let rule = Rule::default();
+        let samples = vec![
+            sample(0, Some(20), 0),
+            sample(1, Some(250), 0),
+            sample(6, Some(20), 40),
+            sample(11, None, 100),
+        ];
+
+        assert!(rule.is_degraded(&samples, at(11)));
+        assert_eq!(rule.degraded_since(&samples, at(11)), Some(at(1)));
+        assert!(!rule.is_degraded(&samples[..3], at(6)));
+        assert!(!rule.is_degraded(&samples[2..], at(11)));
+
+        let mut recovered = samples.clone();
+        recovered.push(sample(12, Some(20), 0));
+        assert!(!rule.is_degraded(&recovered, at(12)));
+    }
+}
@@ -0,0 +1,365 @@
+use crate::schedule;
+use crate::store;
+use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::env;
+
+const DND_FILE: &str = "dnd.json";
+const HELD_FILE: &str = "held.json";
+
+// Telegram refuses messages longer than 4096 characters
+const MAX_MESSAGE: usize = 4000;
+
+/// What to do with alerts during quiet hours or do-not-disturb.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum QuietMode {
+    Silent,
+    Hold,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Priority {
+    Normal,
+    High,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Delivery {
+    Loud,
+    Silent,
+    Hold,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct QuietPeriod {
+    days: Vec<Weekday>,
+    start: NaiveTime,
+    end: NaiveTime,
+}
+
+impl QuietPeriod {
+    /// Periods that end before they start run overnight into the next day.
+    pub fn contains(&self, at: NaiveDateTime) -> bool {
+        let time = at.time();
+
+        if self.start <= self.end {
+            self.days.contains(&at.weekday()) && time >= self.start && time < self.end
+        } else {
+            (self.days.contains(&at.weekday()) && time >= self.start)
+                || (self.days.contains(&at.weekday().pred()) && time < self.end)
+        }
+    }
+}
+
+impl TryFrom<&str> for QuietPeriod {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (days, hours) = value.trim().rsplit_once(' ').ok_or(())?;
+        let (start, end) = hours.split_once('-').ok_or(())?;
+
+        Ok(QuietPeriod {
+            days: schedule::parse_days(days).ok_or(())?,
+            start: schedule::parse_time(start).ok_or(())?,
+            end: schedule::parse_time(end).ok_or(())?,
+        })
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct QuietHours {
+    pub mode: QuietMode,
+    periods: BTreeMap<i64, Vec<QuietPeriod>>,
+}
+
+impl QuietHours {
+    /// Reads `QUIET_MODE` and `QUIET_HOURS`, such as "Mon-Fri 22:00-07:00; Sat,Sun 23:00-09:00".
+    ///
+    /// Periods apply to `CHAT_ID` unless they start with another chat, as in "-100123@daily 21:00-08:00".
+    pub fn from_env() -> Self {
+        let default_chat = env::var("CHAT_ID")
+            .ok()
+            .and_then(|chat| chat.parse().ok())
+            .unwrap_or_default();
+
+        let mode = match env::var("QUIET_MODE") {
+            Ok(mode) if mode.trim().eq_ignore_ascii_case("hold") => QuietMode::Hold,
+            _ => QuietMode::Silent,
+        };
+
+        let mut quiet_hours =
+            QuietHours::parse(&env::var("QUIET_HOURS").unwrap_or_default(), default_chat);
+        quiet_hours.mode = mode;
+
+        quiet_hours
+    }
+
+    pub fn parse(value: &str, default_chat: i64) -> Self {
+        let mut periods: BTreeMap<i64, Vec<QuietPeriod>> = BTreeMap::new();
+
+        for entry in value.split(';').filter(|entry| !entry.trim().is_empty()) {
+            let (chat, entry) = match entry.split_once('@') {
+                Some((chat, entry)) => match chat.trim().parse() {
+                    Ok(chat) => (chat, entry),
+                    Err(_) => {
+                        warn!("Couldn't parse quiet hours chat {}", chat);
+                        continue;
+                    }
+                },
+                None => (default_chat, entry),
+            };
+
+            if let Ok(period) = QuietPeriod::try_from(entry) {
+                periods.entry(chat).or_default().push(period);
+            } else {
+                warn!("Couldn't parse quiet hours {}", entry);
+            }
+        }
+
+        QuietHours {
+            mode: QuietMode::Silent,
+            periods,
+        }
+    }
+
+    pub fn is_quiet(&self, chat: i64, at: NaiveDateTime) -> bool {
+        self.periods
+            .get(&chat)
+            .map(|periods| periods.iter().any(|period| period.contains(at)))
+            .unwrap_or(false)
+    }
+
+    pub fn delivery(
+        &self,
+        dnd: &Dnd,
+        chat: i64,
+        at: NaiveDateTime,
+        priority: Priority,
+    ) -> Delivery {
+        if priority == Priority::High || !(self.is_quiet(chat, at) || dnd.is_active(chat, at)) {
+            return Delivery::Loud;
+        }
+
+        match self.mode {
+            QuietMode::Silent => Delivery::Silent,
+            QuietMode::Hold => Delivery::Hold,
+        }
+    }
+}
+
+/// Ad-hoc silencing set with `/dnd`.
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug)]
+pub struct Dnd {
+    until: BTreeMap<i64, NaiveDateTime>,
+}
+
+impl Dnd {
+    pub fn load() -> Self {
+        store::load(DND_FILE)
+    }
+
+    pub fn save(&self) -> Option<()> {
+        store::save(DND_FILE, self)
+    }
+
+    /// Silences `chat` for `duration`, unless that ends too far in the future to keep.
+    pub fn start(
+        &mut self,
+        chat: i64,
+        at: NaiveDateTime,
+        duration: Duration,
+    ) -> Option<NaiveDateTime> {
+        let until = at.checked_add_signed(duration)?;
+        self.until.insert(chat, until);
+
+        Some(until)
+    }
+
+    pub fn stop(&mut self, chat: i64) -> bool {
+        self.until.remove(&chat).is_some()
+    }
+
+    pub fn until(&self, chat: i64, at: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.until.get(&chat).copied().filter(|until| *until > at)
+    }
+
+    pub fn is_active(&self, chat: i64, at: NaiveDateTime) -> bool {
+        self.until(chat, at).is_some()
+    }
+}
+
+/// Alerts kept back during quiet hours, for the morning digest.
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug)]
+pub struct Held {
+    messages: BTreeMap<i64, Vec<String>>,
+}
+
+impl Held {
+    pub fn load() -> Self {
+        store::load(HELD_FILE)
+    }
+
+    pub fn save(&self) -> Option<()> {
+        store::save(HELD_FILE, self)
+    }
+
+    pub fn push(&mut self, chat: i64, text: String) {
+        self.messages.entry(chat).or_default().push(text);
+    }
+
+    pub fn chats(&self) -> Vec<i64> {
+        self.messages.keys().copied().collect()
+    }
+
+    pub fn take(&mut self, chat: i64) -> Vec<String> {
+        self.messages.remove(&chat).unwrap_or_default()
+    }
+}
+
+/// Joins held alerts into as few messages as Telegram allows.
+pub fn digest(messages: &[String]) -> Vec<String> {
+    let mut digests = Vec::new();
+    let mut digest = format!(
+        "🌅 {} alerts while notifications were paused:",
+        messages.len()
+    );
+
+    for message in messages {
+        let message = truncate(message, MAX_MESSAGE);
+
+        if digest.len() + message.len() + 2 > MAX_MESSAGE {
+            digests.push(digest);
+            digest = String::new();
+        }
+
+        if !digest.is_empty() {
+            digest.push_str("\n\n");
+        }
+        digest.push_str(&message);
+    }
+
+    digests.push(digest);
+    digests
+}
+
+// cuts on a character boundary, leaving room for the ellipsis
+fn truncate(message: &str, max: usize) -> Cow<'_, str> {
+    if message.len() <= max {
+        return message.into();
+    }
+
+    let mut end = max - '…'.len_utf8();
+    while !message.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{}…", &message[..end]).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    // 5 October 2026 is a Monday
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_overnight_period() {
+        let quiet_hours = QuietHours::parse("Mon-Fri 22:00-07:00", 1);
+
+        assert!(!quiet_hours.is_quiet(1, at(5, 21, 59)));
+        assert!(quiet_hours.is_quiet(1, at(5, 22, 0)));
+        assert!(quiet_hours.is_quiet(1, at(6, 6, 59)));
+        assert!(!quiet_hours.is_quiet(1, at(6, 7, 0)));
+
+        // Friday night runs into Saturday morning, but Saturday night isn't quiet
+        assert!(quiet_hours.is_quiet(1, at(10, 6, 0)));
+        assert!(!quiet_hours.is_quiet(1, at(10, 23, 0)));
+    }
+
+    #[test]
+    fn test_per_chat_periods() {
+        let quiet_hours = QuietHours::parse("Sat,Sun 13:00-15:00; -100123@daily 21:00-08:00", 1);
+
+        assert!(quiet_hours.is_quiet(1, at(10, 14, 0)));
+        assert!(!quiet_hours.is_quiet(1, at(5, 23, 0)));
+        assert!(quiet_hours.is_quiet(-100123, at(5, 23, 0)));
+        assert!(!quiet_hours.is_quiet(2, at(5, 23, 0)));
+    }
+
+    #[test]
+    fn test_bad_periods_are_skipped() {
+        assert_eq!(
+            QuietHours::parse("Someday 22:00-07:00; Mon 25:00-07:00", 1),
+            QuietHours::parse("", 1)
+        );
+    }
+
+    #[test]
+    fn test_delivery() {
+        let mut quiet_hours = QuietHours::parse("daily 22:00-07:00", 1);
+        let mut dnd = Dnd::default();
+
+        assert_eq!(
+            quiet_hours.delivery(&dnd, 1, at(5, 12, 0), Priority::Normal),
+            Delivery::Loud
+        );
+        assert_eq!(
+            quiet_hours.delivery(&dnd, 1, at(5, 23, 0), Priority::Normal),
+            Delivery::Silent
+        );
+        assert_eq!(
+            quiet_hours.delivery(&dnd, 1, at(5, 23, 0), Priority::High),
+            Delivery::Loud
+        );
+
+        quiet_hours.mode = QuietMode::Hold;
+        dnd.start(1, at(5, 12, 0), Duration::hours(2));
+
+        assert_eq!(
+            quiet_hours.delivery(&dnd, 1, at(5, 13, 0), Priority::Normal),
+            Delivery::Hold
+        );
+        assert_eq!(
+            quiet_hours.delivery(&dnd, 1, at(5, 14, 0), Priority::Normal),
+            Delivery::Loud
+        );
+    }
+
+    #[test]
+    fn test_dnd_too_long() {
+        let mut dnd = Dnd::default();
+        let duration = schedule::parse_duration("100000000d").unwrap();
+
+        assert_eq!(dnd.start(1, at(5, 12, 0), duration), None);
+        assert!(!dnd.is_active(1, at(5, 12, 0)));
+    }
+
+    #[test]
+    fn test_digest_is_split() {
+        let messages: Vec<String> = (0..100).map(|_| "☎️ 0612345678".repeat(10)).collect();
+        let digests = digest(&messages);
+
+        assert!(digests.len() > 1);
+        assert!(digests.iter().all(|digest| digest.len() <= MAX_MESSAGE));
+    }
+
+    #[test]
+    fn test_digest_truncates_long_messages() {
+        let messages = vec!["📵".repeat(MAX_MESSAGE), "☎️ 0612345678".to_string()];
+        let digests = digest(&messages);
+
+        assert_eq!(digests.len(), 3);
+        assert!(digests.iter().all(|digest| digest.len() <= MAX_MESSAGE));
+        assert!(digests[1].ends_with('…'));
+    }
+}
@@ -0,0 +1,577 @@
+use crate::bus::Bus;
+use crate::contacts::Contacts;
+use crate::event::{Event, EventKind};
+use crate::history::{self, Record};
+use crate::notify;
+use crate::quiet::{Priority, QuietPeriod};
+use crate::store;
+use crate::timm::caller::CallerId;
+use crate::timm::calls::PhoneCall;
+use crate::timm::modem::Modem;
+use crate::timm::stats::LineSpeed;
+use crate::timm::tools;
+use chrono::{Duration, NaiveDateTime};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::fs;
+use teloxide::prelude::*;
+use teloxide::types::InlineKeyboardMarkup;
+
+// how long a webhook has to answer
+const WEBHOOK_TIMEOUT: u64 = 10;
+
+// in minutes, so a line that stays slow doesn't reboot the modem at every sample
+const REBOOT_INTERVAL: i64 = 30;
+const REBOOT_FILE: &str = "rule_reboot.json";
+
+/// What a rule does with the events it matches.
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    /// Alerts the bot's chat as usual.
+    Notify,
+    NotifyChat(i64),
+    /// Sends to the bot's chat without a sound.
+    Silent,
+    /// Alerts the bot's chat with a sound, even in quiet hours.
+    Escalate,
+    Reboot,
+    /// Posts the event as JSON.
+    Webhook(String),
+}
+
+/// Matches events on every condition given, and runs its actions; a rule without actions
+/// mutes what it matches.
+#[derive(PartialEq, Eq, Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Rule {
+    pub name: String,
+    pub on: EventKind,
+    /// A caller such as "+39333*" or "anonymous", `*` matching anything.
+    #[serde(default)]
+    pub caller: Option<String>,
+    /// "contacts", "unknown", "vip" or a group of the rules file.
+    #[serde(default)]
+    pub group: Option<String>,
+    #[serde(default)]
+    pub missed: Option<bool>,
+    /// Days and hours, as in "Mon-Fri 22:00-07:00".
+    #[serde(default)]
+    pub during: Option<String>,
+    #[serde(default)]
+    pub speed: Option<LineSpeed>,
+    /// At most this many kbps.
+    #[serde(default)]
+    pub max_download: Option<u32>,
+    #[serde(default)]
+    pub max_upload: Option<u32>,
+    /// At least this many calls from the number within `within` minutes.
+    #[serde(default)]
+    pub calls: Option<usize>,
+    #[serde(default)]
+    pub within: Option<i64>,
+    #[serde(default)]
+    pub actions: Vec<Action>,
+}
+
+/// What the rules need to know beyond the event.
+pub struct Context<'a> {
+    pub contacts: &'a Contacts,
+    /// Every call the modem remembers, for counting.
+    pub phone_calls: &'a [PhoneCall],
+    pub now: NaiveDateTime,
+}
+
+/// The alert rules, read from the JSON file at `RULES_FILE`.
+#[derive(PartialEq, Eq, Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSet {
+    /// Named lists of numbers, for `group`.
+    #[serde(default)]
+    pub groups: BTreeMap<String, BTreeSet<String>>,
+    #[serde(default)]
+    pub rules: Vec<Rule>,
+}
+
+impl RuleSet {
+    pub fn from_env() -> Self {
+        let path = match env::var("RULES_FILE") {
+            Ok(path) => path,
+            Err(_) => return RuleSet::default(),
+        };
+
+        match fs::read_to_string(&path).map(|text| RuleSet::parse(&text)) {
+            Ok(Some(rules)) => {
+                info!("Loaded {} alert rules from {}", rules.rules.len(), path);
+                rules
+            }
+            Ok(None) => RuleSet::default(),
+            Err(err) => {
+                warn!("Couldn't read alert rules {}: {}", path, err);
+                RuleSet::default()
+            }
+        }
+    }
+
+    /// Reads the rules, refusing them all if one can't be understood.
+    pub fn parse(text: &str) -> Option<Self> {
+        let rules: RuleSet = serde_json::from_str(text)
+            .map_err(|err| warn!("Couldn't parse alert rules: {}", err))
+            .ok()?;
+
+        for rule in &rules.rules {
+            if rule
+                .during
+                .as_deref()
+                .is_some_and(|during| QuietPeriod::try_from(during).is_err())
+            {
+                warn!("Couldn't parse when rule {} applies", rule.name);
+                return None;
+            }
+
+            if rule.calls.is_some() != rule.within.is_some() {
+                warn!("Rule {} needs both calls and within", rule.name);
+                return None;
+            }
+        }
+
+        Some(rules)
+    }
+
+    pub fn matching(&self, event: &Event, context: &Context) -> Vec<&Rule> {
+        self.rules
+            .iter()
+            .filter(|rule| rule.matches(event, context, &self.groups))
+            .collect()
+    }
+
+    /// Runs the actions of the rules matching `event`, or returns false when none match so
+    /// the monitor alerts as it always has.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn apply(
+        &self,
+        bot: &Bot,
+        chat_id: ChatId,
+        modem: &Modem,
+        bus: &Bus,
+        event: &Event,
+        context: &Context<'_>,
+        text: &str,
+        keyboard: Option<InlineKeyboardMarkup>,
+    ) -> bool {
+        let rules = self.matching(event, context);
+        if rules.is_empty() {
+            return false;
+        }
+
+        let mut done: Vec<&Action> = Vec::new();
+
+        for rule in rules {
+            debug!("Rule {} matches {:?}", rule.name, event.kind());
+
+            for action in &rule.actions {
+                // two rules asking for the same thing get it once
+                if done.contains(&action) {
+                    continue;
+                }
+                done.push(action);
+
+                if act(
+                    bot,
+                    chat_id,
+                    modem,
+                    bus,
+                    event,
+                    rule,
+                    action,
+                    text,
+                    keyboard.clone(),
+                )
+                .await
+                .is_none()
+                {
+                    warn!("Couldn't run {:?} for rule {}", action, rule.name);
+                }
+            }
+        }
+
+        true
+    }
+}
+
+impl Rule {
+    pub fn matches(
+        &self,
+        event: &Event,
+        context: &Context,
+        groups: &BTreeMap<String, BTreeSet<String>>,
+    ) -> bool {
+        if event.kind() != self.on {
+            return false;
+        }
+
+        let phone_call = event.phone_call();
+        let stats = event.stats();
+        let at = phone_call.map_or(context.now, |phone_call| phone_call.when);
+
+        // a condition on calls never matches line events, and the other way round
+        let call_matches = |check: &dyn Fn(&PhoneCall) -> bool| phone_call.is_some_and(check);
+        let line_matches =
+            |check: &dyn Fn(&crate::timm::stats::LineStats) -> bool| stats.is_some_and(check);
+
+        self.caller
+            .as_ref()
+            .is_none_or(|pattern| call_matches(&|phone_call| glob(pattern, &phone_call.who.key())))
+            && self.group.as_ref().is_none_or(|group| {
+                call_matches(&|phone_call| in_group(group, &phone_call.who, context, groups))
+            })
+            && self
+                .missed
+                .is_none_or(|missed| call_matches(&|phone_call| phone_call.missed == missed))
+            && self.during.as_deref().is_none_or(|during| {
+                QuietPeriod::try_from(during).is_ok_and(|period| period.contains(at))
+            })
+            && self
+                .speed
+                .is_none_or(|speed| line_matches(&|stats| stats.speed == speed))
+            && self
+                .max_download
+                .is_none_or(|max| line_matches(&|stats| stats.download <= max))
+            && self
+                .max_upload
+                .is_none_or(|max| line_matches(&|stats| stats.upload <= max))
+            && self.calls.is_none_or(|calls| {
+                call_matches(&|phone_call| {
+                    count_calls(phone_call, context.phone_calls, self.within.unwrap_or(0)) >= calls
+                })
+            })
+    }
+}
+
+/// Matches `*` against any run of characters, and the rest as is, ignoring case.
+fn glob(pattern: &str, text: &str) -> bool {
+    let (pattern, text) = (pattern.to_lowercase(), text.to_lowercase());
+    let mut parts = pattern.split('*');
+    let first = parts.next().unwrap_or_default();
+
+    let Some(mut rest) = text.strip_prefix(first) else {
+        return false;
+    };
+
+    let parts: Vec<&str> = parts.collect();
+    for (index, part) in parts.iter().enumerate() {
+        if index == parts.len() - 1 {
+            return rest.ends_with(part);
+        }
+
+        match rest.find(part) {
+            Some(found) => rest = &rest[found + part.len()..],
+            None => return false,
+        }
+    }
+
+    rest.is_empty()
+}
+
+fn in_group(
+    group: &str,
+    caller: &CallerId,
+    context: &Context,
+    groups: &BTreeMap<String, BTreeSet<String>>,
+) -> bool {
+    let key = caller.key();
+
+    match group {
+        "contacts" => context.contacts.name(&key).is_some(),
+        "unknown" => context.contacts.name(&key).is_none(),
+        "vip" => context.contacts.is_vip(&key),
+        group => groups.get(group).is_some_and(|numbers| {
+            numbers
+                .iter()
+                .any(|number| CallerId::from(number.as_str()).key() == key)
+        }),
+    }
+}
+
+fn count_calls(phone_call: &PhoneCall, phone_calls: &[PhoneCall], minutes: i64) -> usize {
+    // withheld and unknown callers are never the same one twice
+    if phone_call.who.number().is_none() {
+        return 0;
+    }
+
+    let since = phone_call.when - Duration::minutes(minutes);
+
+    phone_calls
+        .iter()
+        .filter(|other| other.who == phone_call.who)
+        .filter(|other| other.when > since && other.when <= phone_call.when)
+        .count()
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn act(
+    bot: &Bot,
+    chat_id: ChatId,
+    modem: &Modem,
+    bus: &Bus,
+    event: &Event,
+    rule: &Rule,
+    action: &Action,
+    text: &str,
+    keyboard: Option<InlineKeyboardMarkup>,
+) -> Option<()> {
+    match action {
+        Action::Notify => {
+            notify::alert(bot, chat_id, text.to_string(), keyboard, Priority::Normal).await
+        }
+        Action::NotifyChat(other) => {
+            notify::alert(
+                bot,
+                ChatId(*other),
+                text.to_string(),
+                None,
+                Priority::Normal,
+            )
+            .await
+        }
+        Action::Silent => {
+            let mut message = bot
+                .send_message(chat_id, text.to_string())
+                .disable_notification(true);
+            if let Some(keyboard) = keyboard {
+                message = message.reply_markup(keyboard);
+            }
+
+            message.await.ok().map(|_| ())
+        }
+        Action::Escalate => {
+            notify::alert(
+                bot,
+                chat_id,
+                format!("🚨 {}", text),
+                keyboard,
+                Priority::High,
+            )
+            .await
+        }
+        Action::Reboot => {
+            // rebooting announces itself, which mustn't reboot again
+            if event.kind() == EventKind::RebootIssued {
+                warn!("Rule {} would reboot the modem over and over", rule.name);
+                return None;
+            }
+
+            let at = history::now();
+            let last: Option<NaiveDateTime> = store::load(REBOOT_FILE);
+            if last.is_some_and(|last| at - last < Duration::minutes(REBOOT_INTERVAL)) {
+                info!("Rule {} rebooted the modem not long ago", rule.name);
+                return Some(());
+            }
+
+            info!("Rebooting the modem for rule {}", rule.name);
+            tools::reboot(modem).await?;
+            history::append(&Record::Reboot { at });
+            if store::save(REBOOT_FILE, &at).is_none() {
+                warn!("Couldn't save when the rules rebooted the modem.");
+            }
+            bus.publish(Event::RebootIssued {
+                at,
+                by: format!("rule {}", rule.name),
+            });
+
+            notify::alert(
+                bot,
+                chat_id,
+                format!("🔄 Rebooting the modem, as rule {} says.", rule.name),
+                None,
+                Priority::Normal,
+            )
+            .await
+        }
+        Action::Webhook(url) => reqwest::Client::new()
+            .post(url)
+            .timeout(std::time::Duration::from_secs(WEBHOOK_TIMEOUT))
+            .json(&serde_json::json!({
+                "rule": rule.name,
+                "text": text,
+                "event": event,
+            }))
+            .send()
+            .await
+            .ok()?
+            .error_for_status()
+            .ok()
+            .map(|_| ()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timm::stats::LineStats;
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    // a Monday
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 5)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn call(who: &str, when: NaiveDateTime) -> PhoneCall {
+        PhoneCall {
+            who: CallerId::from(who),
+            when,
+            missed: true,
+        }
+    }
+
+    fn stats(download: u32, upload: u32, speed: LineSpeed) -> LineStats {
+        LineStats {
+            ip: "79.12.34.56".to_string(),
+            download,
+            upload,
+            speed,
+        }
+    }
+
+    const RULES: &str = r#"{
+        "groups": { "family": ["333 1234567"] },
+        "rules": [
+            { "name": "family at night", "on": "new_call", "group": "family",
+              "during": "daily 22:00-07:00", "actions": ["escalate"] },
+            { "name": "call centres", "on": "new_call", "caller": "+3902*", "actions": [] },
+            { "name": "urgent", "on": "new_call", "calls": 2, "within": 10,
+              "actions": [{ "notify_chat": 43 }, "notify"] },
+            { "name": "crawling", "on": "line_sample", "max_download": 2000,
+              "actions": ["reboot", { "webhook": "http://localhost/hook" }] }
+        ]
+    }"#;
+
+    fn names(rules: &RuleSet, event: &Event, phone_calls: &[PhoneCall]) -> Vec<String> {
+        let contacts = Contacts::default();
+        let context = Context {
+            contacts: &contacts,
+            phone_calls,
+            now: at(12, 0),
+        };
+
+        rules
+            .matching(event, &context)
+            .iter()
+            .map(|rule| rule.name.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let rules = RuleSet::parse(RULES).unwrap();
+
+        assert_eq!(rules.rules.len(), 4);
+        assert_eq!(
+            rules.rules[2].actions,
+            vec![Action::NotifyChat(43), Action::Notify]
+        );
+        assert_eq!(
+            RuleSet::parse(r#"{"rules": [{"name": "x", "on": "new_call", "during": "soon"}]}"#),
+            None
+        );
+        assert_eq!(
+            RuleSet::parse(r#"{"rules": [{"name": "x", "on": "new_call", "calls": 3}]}"#),
+            None
+        );
+        assert_eq!(
+            RuleSet::parse(r#"{"rules": [{"name": "x", "on": "new_call", "colour": "red"}]}"#),
+            None
+        );
+    }
+
+    #[test]
+    fn test_calls() {
+        let rules = RuleSet::parse(RULES).unwrap();
+
+        let night = call("3331234567", at(23, 0));
+        assert_eq!(
+            names(&rules, &Event::NewCall(night.clone()), &[night]),
+            vec!["family at night"]
+        );
+
+        let day = call("3331234567", at(12, 0));
+        assert!(names(&rules, &Event::NewCall(day.clone()), &[day]).is_empty());
+
+        let office = call("0212345678", at(12, 0));
+        assert_eq!(
+            names(&rules, &Event::NewCall(office.clone()), &[office]),
+            vec!["call centres"]
+        );
+
+        let phone_calls = vec![
+            call("0612345678", at(11, 55)),
+            call("0612345678", at(12, 0)),
+        ];
+        assert_eq!(
+            names(
+                &rules,
+                &Event::NewCall(phone_calls[1].clone()),
+                &phone_calls
+            ),
+            vec!["urgent"]
+        );
+    }
+
+    #[test]
+    fn test_withheld_calls_are_not_counted() {
+        let rules = RuleSet::parse(RULES).unwrap();
+        let phone_calls = vec![call("Anonimo", at(11, 55)), call("Privato", at(12, 0))];
+
+        assert!(names(
+            &rules,
+            &Event::NewCall(phone_calls[1].clone()),
+            &phone_calls
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_line() {
+        let rules = RuleSet::parse(RULES).unwrap();
+
+        assert_eq!(
+            names(
+                &rules,
+                &Event::LineSample(stats(1500, 900, LineSpeed::Slow)),
+                &[]
+            ),
+            vec!["crawling"]
+        );
+        assert!(names(
+            &rules,
+            &Event::LineSample(stats(12000, 900, LineSpeed::Normal)),
+            &[]
+        )
+        .is_empty());
+        // the same reading, as another kind of event
+        assert!(names(
+            &rules,
+            &Event::SpeedStateChanged {
+                stats: stats(1500, 900, LineSpeed::Slow),
+                reason: None,
+            },
+            &[]
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_glob() {
+        assert!(glob("+39333*", "+393331234567"));
+        assert!(glob("*4567", "+393331234567"));
+        assert!(glob("+39*12*67", "+393331234567"));
+        assert!(glob("Anonymous", "anonymous"));
+        assert!(!glob("+3906*", "+393331234567"));
+        assert!(!glob("+39333", "+393331234567"));
+    }
+}
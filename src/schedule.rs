@@ -0,0 +1,131 @@
+use chrono::{Duration, NaiveTime, Weekday};
+
+/// Reads durations such as "2h", "30m", "1h30m" or "7d".
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let value = value.trim().to_lowercase();
+    let mut total = Duration::zero();
+    let mut amount = String::new();
+
+    for ch in value.chars() {
+        if ch.is_ascii_digit() {
+            amount.push(ch);
+            continue;
+        }
+
+        let number: i64 = amount.parse().ok()?;
+        amount.clear();
+
+        // anything too long for chrono is refused rather than panicking
+        let part = match ch {
+            'd' => Duration::try_days(number)?,
+            'h' => Duration::try_hours(number)?,
+            'm' => Duration::try_minutes(number)?,
+            's' => Duration::try_seconds(number)?,
+            _ => return None,
+        };
+        total = total.checked_add(&part)?;
+    }
+
+    // a trailing number without a unit is ambiguous
+    if !amount.is_empty() || total <= Duration::zero() {
+        return None;
+    }
+
+    Some(total)
+}
+
+/// Writes a duration the way people say it, such as "2h 5m".
+pub fn format_duration(duration: Duration) -> String {
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+
+    match (days, hours, minutes) {
+        (0, 0, minutes) => format!("{}m", minutes),
+        (0, hours, 0) => format!("{}h", hours),
+        (0, hours, minutes) => format!("{}h {}m", hours, minutes),
+        (days, 0, _) => format!("{}d", days),
+        (days, hours, _) => format!("{}d {}h", days, hours),
+    }
+}
+
+pub fn parse_time(value: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(value.trim(), "%H:%M").ok()
+}
+
+/// Reads "Mon-Fri", "Sat,Sun", "Fri-Mon" or "daily" into the days they cover.
+pub fn parse_days(value: &str) -> Option<Vec<Weekday>> {
+    let value = value.trim();
+
+    if value.eq_ignore_ascii_case("daily") || value == "*" {
+        return parse_days("Mon-Sun");
+    }
+
+    let mut days = Vec::new();
+
+    for part in value.split(',') {
+        if let Some((first, last)) = part.split_once('-') {
+            let mut day: Weekday = first.trim().parse().ok()?;
+            let last: Weekday = last.trim().parse().ok()?;
+
+            while day != last {
+                days.push(day);
+                day = day.succ();
+            }
+            days.push(last);
+        } else {
+            days.push(part.trim().parse().ok()?);
+        }
+    }
+
+    Some(days)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("2h"), Some(Duration::hours(2)));
+        assert_eq!(parse_duration("1h30m"), Some(Duration::minutes(90)));
+        assert_eq!(parse_duration("7d"), Some(Duration::days(7)));
+        assert_eq!(parse_duration("2"), None);
+        assert_eq!(parse_duration("0m"), None);
+        assert_eq!(parse_duration("soon"), None);
+        assert_eq!(parse_duration("99999999999999999999d"), None);
+        assert_eq!(parse_duration("106751991168d"), None);
+        assert_eq!(parse_duration("106751991167d106751991167d"), None);
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::minutes(17)), "17m");
+        assert_eq!(format_duration(Duration::minutes(125)), "2h 5m");
+        assert_eq!(format_duration(Duration::hours(26)), "1d 2h");
+    }
+
+    #[test]
+    fn test_parse_days() {
+        assert_eq!(
+            parse_days("Mon-Fri"),
+            Some(vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri
+            ])
+        );
+        assert_eq!(
+            parse_days("Sat,Sun"),
+            Some(vec![Weekday::Sat, Weekday::Sun])
+        );
+        assert_eq!(
+            parse_days("Fri-Mon"),
+            Some(vec![Weekday::Fri, Weekday::Sat, Weekday::Sun, Weekday::Mon])
+        );
+        assert_eq!(parse_days("daily").map(|days| days.len()), Some(7));
+        assert_eq!(parse_days("Someday"), None);
+    }
+}
@@ -0,0 +1,419 @@
+use crate::contacts::Contacts;
+use crate::store;
+use crate::timm::caller::{self, CallerId};
+use crate::timm::calls::PhoneCall;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::env;
+use std::fmt::{Display, Formatter};
+use std::fs;
+
+const FILE: &str = "blocklist.json";
+
+/// Numbers blocked with `/block` or marked as spam from a call notification.
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug)]
+pub struct Blocklist {
+    numbers: BTreeSet<String>,
+    #[serde(default)]
+    reported: BTreeSet<String>,
+}
+
+impl Blocklist {
+    pub fn load() -> Self {
+        let mut blocklist: Blocklist = store::load(FILE);
+
+        if blocklist.migrate() {
+            info!("Moving the blocklist to E.164 numbers");
+            blocklist.save();
+        }
+
+        blocklist
+    }
+
+    pub fn save(&self) -> Option<()> {
+        store::save(FILE, self)
+    }
+
+    // earlier versions kept numbers as typed, or with only their digits
+    fn migrate(&mut self) -> bool {
+        let numbers = rekey(&self.numbers);
+        let reported = rekey(&self.reported);
+        let changed = numbers != self.numbers || reported != self.reported;
+
+        self.numbers = numbers;
+        self.reported = reported;
+
+        changed
+    }
+
+    pub fn contains(&self, number: &str) -> bool {
+        self.numbers.contains(&key(number))
+    }
+
+    pub fn is_reported(&self, number: &str) -> bool {
+        self.reported.contains(&key(number))
+    }
+
+    pub fn insert(&mut self, number: &str) -> bool {
+        self.numbers.insert(key(number))
+    }
+
+    pub fn report(&mut self, number: &str) -> bool {
+        self.reported.insert(key(number))
+    }
+
+    pub fn remove(&mut self, number: &str) -> bool {
+        let number = key(number);
+
+        // both have to be removed, so don't short circuit
+        self.numbers.remove(&number) | self.reported.remove(&number)
+    }
+}
+
+/// Suspected spam calls are either announced with a warning or only logged. Calls from
+/// blocked numbers are never announced.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum SpamMode {
+    Tag,
+    Silent,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum SpamReason {
+    Blocked,
+    Reported,
+    Prefix(String),
+    Community,
+    Repeated(usize, i64),
+}
+
+impl SpamReason {
+    /// Whether the number is only suspected for calling often.
+    pub fn is_repeat(&self) -> bool {
+        matches!(self, SpamReason::Repeated(_, _))
+    }
+}
+
+impl Display for SpamReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SpamReason::Blocked => write!(f, "🚫 Blocked number"),
+            SpamReason::Reported => write!(f, "🚫 Reported as spam"),
+            SpamReason::Prefix(prefix) => write!(f, "⚠️ Call centre number ({}…)", prefix),
+            SpamReason::Community => write!(f, "⚠️ On the community blocklist"),
+            SpamReason::Repeated(count, days) => {
+                write!(f, "⚠️ Unknown number, {} calls in {} days", count, days)
+            }
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug)]
+pub struct SpamFilter {
+    pub mode: SpamMode,
+    prefixes: Vec<String>,
+    community: BTreeSet<String>,
+    repeat: Option<(usize, i64)>,
+}
+
+impl Default for SpamFilter {
+    fn default() -> Self {
+        SpamFilter {
+            mode: SpamMode::Tag,
+            prefixes: Vec::new(),
+            community: BTreeSet::new(),
+            repeat: Some((3, 7)),
+        }
+    }
+}
+
+impl SpamFilter {
+    /// Reads `SPAM_MODE`, `SPAM_PREFIXES`, `SPAM_COMMUNITY_FILE` and `SPAM_REPEAT`.
+    pub fn from_env() -> Self {
+        let mut filter = SpamFilter::default();
+
+        if let Ok(mode) = env::var("SPAM_MODE") {
+            filter.mode = if mode.trim().eq_ignore_ascii_case("silent") {
+                SpamMode::Silent
+            } else {
+                SpamMode::Tag
+            };
+        }
+
+        if let Ok(prefixes) = env::var("SPAM_PREFIXES") {
+            filter.prefixes = prefixes
+                .split(',')
+                .map(caller::e164)
+                .filter(|prefix| !prefix.is_empty())
+                .collect();
+        }
+
+        if let Ok(path) = env::var("SPAM_COMMUNITY_FILE") {
+            match fs::read_to_string(&path) {
+                Ok(text) => filter.community = parse_list(&text),
+                Err(err) => warn!("Couldn't read community blocklist {}: {}", path, err),
+            }
+            debug!(
+                "There are {} numbers in the community blocklist.",
+                filter.community.len()
+            );
+        }
+
+        if let Ok(repeat) = env::var("SPAM_REPEAT") {
+            filter.repeat = parse_repeat(&repeat);
+        }
+
+        filter
+    }
+
+    /// Decides whether `phone_call` is spam, `phone_calls` being every call the modem remembers.
+    pub fn classify(
+        &self,
+        blocklist: &Blocklist,
+        contacts: &Contacts,
+        phone_call: &PhoneCall,
+        phone_calls: &[PhoneCall],
+    ) -> Option<SpamReason> {
+        let number = phone_call.who.key();
+
+        if blocklist.contains(&number) {
+            return Some(SpamReason::Blocked);
+        }
+
+        if blocklist.is_reported(&number) {
+            return Some(SpamReason::Reported);
+        }
+
+        // saved contacts are never spam
+        if contacts.name(&number).is_some() {
+            return None;
+        }
+
+        if let Some(prefix) = phone_call.who.number().and_then(|number| {
+            self.prefixes
+                .iter()
+                .find(|prefix| number.starts_with(prefix.as_str()))
+        }) {
+            return Some(SpamReason::Prefix(prefix.to_string()));
+        }
+
+        if self.community.contains(&number) {
+            return Some(SpamReason::Community);
+        }
+
+        // withheld and unknown callers are never the same one twice
+        if let Some((times, days)) = self.repeat.filter(|_| phone_call.who.number().is_some()) {
+            let since = phone_call.when - Duration::days(days);
+            let count = phone_calls
+                .iter()
+                .filter(|other| other.who == phone_call.who)
+                .filter(|other| other.when > since && other.when <= phone_call.when)
+                .count();
+
+            if count >= times {
+                return Some(SpamReason::Repeated(count, days));
+            }
+        }
+
+        None
+    }
+}
+
+// "anonymous" blocks withheld calls
+fn key(number: &str) -> String {
+    CallerId::from(number).key()
+}
+
+fn rekey(numbers: &BTreeSet<String>) -> BTreeSet<String> {
+    numbers
+        .iter()
+        .filter(|number| !number.trim().is_empty())
+        .map(|number| key(number))
+        .collect()
+}
+
+/// Reads a blocklist with one number per line, `#` comments and optional CSV columns.
+pub fn parse_list(text: &str) -> BTreeSet<String> {
+    text.lines()
+        .map(|line| line.split('#').next().unwrap_or_default())
+        .map(|line| line.split([',', ';', '\t']).next().unwrap_or_default())
+        .filter(|line| !line.trim().is_empty())
+        .map(key)
+        .collect()
+}
+
+/// Reads "3/7" as three calls in seven days, or "off".
+fn parse_repeat(value: &str) -> Option<(usize, i64)> {
+    let (times, days) = value.trim().split_once('/')?;
+
+    match (times.trim().parse(), days.trim().parse()) {
+        (Ok(times), Ok(days)) if times > 0 && days > 0 => Some((times, days)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Local;
+
+    use super::*;
+
+    fn call(who: &str, days_ago: i64) -> PhoneCall {
+        PhoneCall {
+            who: CallerId::from(who),
+            when: Local::now().naive_local() - Duration::days(days_ago),
+            missed: false,
+        }
+    }
+
+    #[test]
+    fn test_not_spam() {
+        let filter = SpamFilter::default();
+        let phone_call = call("06 1234 5678", 0);
+
+        assert_eq!(
+            filter.classify(
+                &Blocklist::default(),
+                &Contacts::default(),
+                &phone_call,
+                std::slice::from_ref(&phone_call)
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_blocked_and_reported() {
+        let filter = SpamFilter::default();
+        let mut blocklist = Blocklist::default();
+        blocklist.insert("0612345678");
+        blocklist.report("0287654321");
+
+        assert_eq!(
+            filter.classify(
+                &blocklist,
+                &Contacts::default(),
+                &call("06 1234-5678", 0),
+                &[]
+            ),
+            Some(SpamReason::Blocked)
+        );
+        assert_eq!(
+            filter.classify(
+                &blocklist,
+                &Contacts::default(),
+                &call("0287654321", 0),
+                &[]
+            ),
+            Some(SpamReason::Reported)
+        );
+
+        assert!(blocklist.remove("0612345678"));
+        assert!(!blocklist.contains("0612345678"));
+    }
+
+    #[test]
+    fn test_migrate() {
+        let mut blocklist: Blocklist = serde_json::from_str(
+            r#"{"numbers": ["06 1234-5678", "+390287654321"], "reported": ["0211112222", ""]}"#,
+        )
+        .unwrap();
+
+        assert!(blocklist.migrate());
+        assert!(blocklist.contains("0612345678"));
+        assert!(blocklist.contains("02 8765 4321"));
+        assert!(blocklist.is_reported("+39 02 1111 2222"));
+        // an empty entry would have blocked every unknown caller
+        assert!(!blocklist.is_reported("Non disponibile"));
+
+        assert!(!blocklist.migrate());
+    }
+
+    #[test]
+    fn test_prefix_and_community() {
+        let filter = SpamFilter {
+            prefixes: vec![caller::e164("0418")],
+            community: parse_list("# known spammers\n02 1111 2222, telemarketing\n\n"),
+            ..SpamFilter::default()
+        };
+
+        assert_eq!(
+            filter.classify(
+                &Blocklist::default(),
+                &Contacts::default(),
+                &call("0418123456", 0),
+                &[]
+            ),
+            Some(SpamReason::Prefix("+390418".to_string()))
+        );
+        assert_eq!(
+            filter.classify(
+                &Blocklist::default(),
+                &Contacts::default(),
+                &call("0211112222", 0),
+                &[]
+            ),
+            Some(SpamReason::Community)
+        );
+    }
+
+    #[test]
+    fn test_repeated_unknown_caller() {
+        let filter = SpamFilter::default();
+        let phone_call = call("3331234567", 0);
+        let phone_calls = vec![
+            phone_call.clone(),
+            call("3331234567", 2),
+            call("3331234567", 5),
+            call("3331234567", 9),
+        ];
+
+        assert_eq!(
+            filter.classify(
+                &Blocklist::default(),
+                &Contacts::default(),
+                &phone_call,
+                &phone_calls
+            ),
+            Some(SpamReason::Repeated(3, 7))
+        );
+
+        let mut contacts = Contacts::default();
+        contacts.insert("3331234567", "Courier");
+
+        assert_eq!(
+            filter.classify(&Blocklist::default(), &contacts, &phone_call, &phone_calls),
+            None
+        );
+    }
+
+    #[test]
+    fn test_repeated_withheld_callers() {
+        let filter = SpamFilter::default();
+        let phone_call = call("Anonimo", 0);
+        let phone_calls = vec![
+            phone_call.clone(),
+            call("Privato", 1),
+            call("anonymous", 2),
+            call("Anonimo", 3),
+        ];
+
+        assert_eq!(
+            filter.classify(
+                &Blocklist::default(),
+                &Contacts::default(),
+                &phone_call,
+                &phone_calls
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_repeat() {
+        assert_eq!(parse_repeat("4/10"), Some((4, 10)));
+        assert_eq!(parse_repeat("off"), None);
+        assert_eq!(parse_repeat("0/7"), None);
+    }
+}
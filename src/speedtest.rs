@@ -0,0 +1,176 @@
+use crate::timm::stats::LineStats;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::time::{Duration, Instant};
+
+/// A server that sends and takes throwaway data, answering `GET /__down?bytes=N`
+/// and `POST /__up` the way speed.cloudflare.com does.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Endpoint {
+    pub url: String,
+    pub bytes: usize,
+    pub timeout: Duration,
+}
+
+impl Default for Endpoint {
+    fn default() -> Self {
+        Endpoint {
+            url: "https://speed.cloudflare.com".to_string(),
+            bytes: 10_000_000,
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Endpoint {
+    pub fn new(url: &str) -> Self {
+        Endpoint {
+            url: url.trim_end_matches('/').to_string(),
+            ..Endpoint::default()
+        }
+    }
+
+    /// Reads `SPEEDTEST_URL` and `SPEEDTEST_BYTES`, how much to download and upload.
+    pub fn from_env() -> Self {
+        let mut endpoint = match env::var("SPEEDTEST_URL") {
+            Ok(url) => Endpoint::new(&url),
+            Err(_) => Endpoint::default(),
+        };
+
+        if let Some(bytes) = env::var("SPEEDTEST_BYTES")
+            .ok()
+            .and_then(|bytes| bytes.parse().ok())
+        {
+            endpoint.bytes = bytes;
+        }
+
+        endpoint
+    }
+
+    fn client(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .unwrap_or_default()
+    }
+
+    /// Measures the download speed in kbps.
+    pub async fn download(&self) -> Option<u32> {
+        let start = Instant::now();
+        let mut resp = self
+            .client()
+            .get(format!("{}/__down?bytes={}", self.url, self.bytes))
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .ok()?;
+
+        let mut received = 0;
+        while let Some(chunk) = resp.chunk().await.ok()? {
+            received += chunk.len();
+        }
+
+        debug!("Downloaded {} bytes in {:?}", received, start.elapsed());
+        kbps(received, start.elapsed())
+    }
+
+    /// Measures the upload speed in kbps.
+    pub async fn upload(&self) -> Option<u32> {
+        let start = Instant::now();
+        self.client()
+            .post(format!("{}/__up", self.url))
+            .body(vec![0; self.bytes])
+            .send()
+            .await
+            .and_then(|resp| resp.error_for_status())
+            .ok()?;
+
+        debug!("Uploaded {} bytes in {:?}", self.bytes, start.elapsed());
+        kbps(self.bytes, start.elapsed())
+    }
+
+    pub async fn run(&self, at: NaiveDateTime) -> SpeedTest {
+        SpeedTest {
+            at,
+            download: self.download().await,
+            upload: self.upload().await,
+        }
+    }
+}
+
+fn kbps(bytes: usize, elapsed: Duration) -> Option<u32> {
+    let seconds = elapsed.as_secs_f64();
+
+    (bytes > 0 && seconds > 0.0).then(|| (bytes as f64 * 8.0 / 1000.0 / seconds) as u32)
+}
+
+/// The throughput measured at one time, in kbps like the modem's figures.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct SpeedTest {
+    pub at: NaiveDateTime,
+    pub download: Option<u32>,
+    pub upload: Option<u32>,
+}
+
+impl SpeedTest {
+    pub fn message(&self, stats: Option<&LineStats>) -> String {
+        let mbps = |kbps: Option<u32>| match kbps {
+            Some(kbps) => format!("{:.1}Mbps", kbps as f64 / 1000.0),
+            None => "failed".to_string(),
+        };
+
+        let measured = format!(
+            "🚀 Measured 🔻 {} 🔺 {}",
+            mbps(self.download),
+            mbps(self.upload)
+        );
+
+        match stats {
+            Some(stats) => format!(
+                "{}\n📶 Modem reports 🔻 {} 🔺 {}",
+                measured,
+                mbps(Some(stats.download)),
+                mbps(Some(stats.upload))
+            ),
+            None => measured,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timm::stats::LineSpeed;
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    #[test]
+    fn test_kbps() {
+        assert_eq!(kbps(1_000_000, Duration::from_secs(2)), Some(4000));
+        assert_eq!(kbps(0, Duration::from_secs(2)), None);
+    }
+
+    #[test]
+    fn test_message() {
+        let speed_test = SpeedTest {
+            at: NaiveDate::from_ymd_opt(2026, 10, 5)
+                .unwrap()
+                .and_hms_opt(14, 0, 0)
+                .unwrap(),
+            download: Some(85300),
+            upload: None,
+        };
+        let stats = LineStats {
+            ip: "192.0.2.1".to_string(),
+            download: 98304,
+            upload: 20480,
+            speed: LineSpeed::Normal,
+        };
+
+        assert_eq!(
+            speed_test.message(Some(&stats)),
+            "🚀 Measured 🔻 85.3Mbps 🔺 failed\n📶 Modem reports 🔻 98.3Mbps 🔺 20.5Mbps"
+        );
+    }
+}
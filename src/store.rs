@@ -0,0 +1,138 @@
+use serde::{de::DeserializeOwned, Serialize};
+use std::env;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Where the bot keeps its state, `DATA_DIR` or `./data` by default.
+pub fn path(name: &str) -> PathBuf {
+    let dir = env::var("DATA_DIR").unwrap_or_else(|_| "data".to_string());
+
+    PathBuf::from(dir).join(name)
+}
+
+/// How many bytes the bot keeps in its data directory.
+pub fn size() -> u64 {
+    fn size_of(path: &Path) -> u64 {
+        match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|entry| size_of(&entry.path()))
+                .sum(),
+            Err(_) => fs::metadata(path).map_or(0, |metadata| metadata.len()),
+        }
+    }
+
+    size_of(&path(""))
+}
+
+pub fn load<T: DeserializeOwned + Default>(name: &str) -> T {
+    let path = path(name);
+
+    if let Ok(data) = fs::read_to_string(&path) {
+        match serde_json::from_str(&data) {
+            Ok(value) => value,
+            Err(err) => {
+                warn!("Couldn't parse {}: {}", path.display(), err);
+                T::default()
+            }
+        }
+    } else {
+        debug!("Nothing stored in {} yet.", path.display());
+        T::default()
+    }
+}
+
+pub fn save<T: Serialize>(name: &str, value: &T) -> Option<()> {
+    let path = path(name);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).ok()?;
+    }
+
+    let data = serde_json::to_string_pretty(value).ok()?;
+
+    if let Err(err) = fs::write(&path, data) {
+        warn!("Couldn't save {}: {}", path.display(), err);
+        return None;
+    }
+
+    Some(())
+}
+
+/// Adds one record to a JSON lines file, without reading what is already there.
+pub fn append<T: Serialize>(name: &str, value: &T) -> Option<()> {
+    let path = path(name);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).ok()?;
+    }
+
+    let line = serde_json::to_string(value).ok()?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .ok()?;
+
+    if let Err(err) = writeln!(file, "{}", line) {
+        warn!("Couldn't append to {}: {}", path.display(), err);
+        return None;
+    }
+
+    Some(())
+}
+
+pub fn load_lines<T: DeserializeOwned>(name: &str) -> Vec<T> {
+    let path = path(name);
+
+    fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(value) => Some(value),
+            Err(err) => {
+                warn!("Skipping bad line in {}: {}", path.display(), err);
+                None
+            }
+        })
+        .collect()
+}
+
+pub fn save_lines<T: Serialize>(name: &str, values: &[T]) -> Option<()> {
+    let path = path(name);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).ok()?;
+    }
+
+    let mut data = String::new();
+    for value in values {
+        data.push_str(&serde_json::to_string(value).ok()?);
+        data.push('\n');
+    }
+
+    if let Err(err) = fs::write(&path, data) {
+        warn!("Couldn't save {}: {}", path.display(), err);
+        return None;
+    }
+
+    Some(())
+}
+
+/// Keeps a copy of a file as it was, such as a page the parsers couldn't read.
+pub fn save_text(name: &str, text: &str) -> Option<PathBuf> {
+    let path = path(name);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).ok()?;
+    }
+
+    if let Err(err) = fs::write(&path, text) {
+        warn!("Couldn't save {}: {}", path.display(), err);
+        return None;
+    }
+
+    Some(path)
+}
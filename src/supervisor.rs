@@ -0,0 +1,119 @@
+use futures::FutureExt;
+use std::any::Any;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use teloxide::prelude::*;
+use tokio::time::{sleep, Duration, Instant};
+
+// in seconds, the wait before restarting a task after its first crash, doubled after each
+// crash in a row
+const BACKOFF_START: u64 = 5;
+// in seconds, the longest wait; a task running this long before crashing had recovered
+const BACKOFF_MAX: u64 = 10 * 60;
+
+/// Keeps a task running, starting it again whenever it stops or panics after a wait that
+/// grows with each crash in a row. Panics are reported to the chat.
+pub async fn supervise<F, Fut>(name: &str, bot: &Bot, chat_id: ChatId, mut task: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = ()>,
+{
+    let mut crashes: u32 = 0;
+
+    loop {
+        let started = Instant::now();
+        let result = AssertUnwindSafe(task()).catch_unwind().await;
+
+        if started.elapsed() >= Duration::from_secs(BACKOFF_MAX) {
+            crashes = 0;
+        }
+        crashes += 1;
+        let wait = backoff(crashes);
+
+        match result {
+            Ok(()) => warn!("{} stopped, restarting in {:?}", name, wait),
+            Err(panic) => {
+                let reason = panic_message(panic.as_ref());
+                error!("{} panicked: {}", name, reason);
+
+                let text = format!(
+                    "💥 {} crashed ({} in a row): {}\nRestarting in {}s.",
+                    name,
+                    crashes,
+                    reason,
+                    wait.as_secs()
+                );
+                if bot.send_message(chat_id, text).await.is_err() {
+                    warn!("Couldn't send the {} crash report.", name);
+                }
+            }
+        }
+
+        sleep(wait).await;
+    }
+}
+
+fn backoff(crashes: u32) -> Duration {
+    let seconds = 2u64
+        .saturating_pow(crashes.saturating_sub(1))
+        .saturating_mul(BACKOFF_START);
+
+    Duration::from_secs(seconds.min(BACKOFF_MAX))
+}
+
+fn panic_message(panic: &(dyn Any + Send)) -> String {
+    if let Some(message) = panic.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = panic.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Waits for Ctrl-C or, on Unix, SIGTERM.
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    let terminate = async {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                warn!("Couldn't listen for SIGTERM: {}", err);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => info!("Got SIGINT"),
+        _ = terminate => info!("Got SIGTERM"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        assert_eq!(backoff(1), Duration::from_secs(5));
+        assert_eq!(backoff(3), Duration::from_secs(20));
+        assert_eq!(backoff(8), Duration::from_secs(BACKOFF_MAX));
+        assert_eq!(backoff(100), Duration::from_secs(BACKOFF_MAX));
+    }
+
+    #[test]
+    fn test_panic_message() {
+        let panic = std::panic::catch_unwind(|| panic!("no {}", "modem")).unwrap_err();
+        assert_eq!(panic_message(panic.as_ref()), "no modem");
+
+        let panic = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(panic_message(panic.as_ref()), "static");
+    }
+}
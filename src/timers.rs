@@ -0,0 +1,190 @@
+use crate::schedule;
+use crate::store;
+use crate::timm::wifi::Network;
+use chrono::{Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const FILE: &str = "timers.json";
+
+/// Settings to change back later, such as the guest Wi-Fi switched on for 2h,
+/// and the port-forwarding rules the bot opened, by public port, with when they expire.
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug)]
+pub struct Timers {
+    #[serde(default)]
+    wifi: BTreeMap<Network, (bool, NaiveDateTime)>,
+    #[serde(default)]
+    ports: BTreeMap<u16, NaiveDateTime>,
+}
+
+impl Timers {
+    pub fn load() -> Self {
+        store::load(FILE)
+    }
+
+    pub fn save(&self) -> Option<()> {
+        store::save(FILE, self)
+    }
+
+    /// Switches the network on or off at `at`.
+    pub fn set_wifi(&mut self, network: Network, enabled: bool, at: NaiveDateTime) {
+        self.wifi.insert(network, (enabled, at));
+    }
+
+    pub fn cancel_wifi(&mut self, network: Network) -> bool {
+        self.wifi.remove(&network).is_some()
+    }
+
+    /// Takes the Wi-Fi switches whose time has come.
+    pub fn take_due_wifi(&mut self, now: NaiveDateTime) -> Vec<(Network, bool, NaiveDateTime)> {
+        let due: Vec<(Network, bool, NaiveDateTime)> = self
+            .wifi
+            .iter()
+            .filter(|(_, (_, at))| *at <= now)
+            .map(|(network, (enabled, at))| (*network, *enabled, *at))
+            .collect();
+
+        for (network, _, _) in &due {
+            self.wifi.remove(network);
+        }
+
+        due
+    }
+
+    pub fn set_port(&mut self, external: u16, expires: NaiveDateTime) {
+        self.ports.insert(external, expires);
+    }
+
+    pub fn cancel_port(&mut self, external: u16) -> bool {
+        self.ports.remove(&external).is_some()
+    }
+
+    /// When a port the bot opened expires, or None if the bot didn't open it.
+    pub fn port(&self, external: u16) -> Option<NaiveDateTime> {
+        self.ports.get(&external).copied()
+    }
+
+    /// Takes the ports whose rules have expired.
+    pub fn take_due_ports(&mut self, now: NaiveDateTime) -> Vec<(u16, NaiveDateTime)> {
+        let due: Vec<(u16, NaiveDateTime)> = self
+            .ports
+            .iter()
+            .filter(|(_, expires)| **expires <= now)
+            .map(|(external, expires)| (*external, *expires))
+            .collect();
+
+        for (external, _) in &due {
+            self.ports.remove(external);
+        }
+
+        due
+    }
+}
+
+/// Reads "on", "off" or either followed by how long for, such as "on 2h".
+pub fn parse_switch(value: &str) -> Option<(bool, Option<Duration>)> {
+    let mut words = value.split_whitespace();
+
+    let enabled = match words.next()?.to_lowercase().as_str() {
+        "on" => true,
+        "off" => false,
+        _ => return None,
+    };
+
+    let duration = match words.next() {
+        Some(duration) => Some(schedule::parse_duration(duration)?),
+        None => None,
+    };
+
+    if words.next().is_some() {
+        return None;
+    }
+
+    Some((enabled, duration))
+}
+
+/// Splits how long for off the end of a command, as in "2222 192.168.1.50:22 4h".
+pub fn split_duration(value: &str) -> (&str, Option<Duration>) {
+    let value = value.trim();
+
+    match value.rsplit_once(' ') {
+        Some((rest, last)) => match schedule::parse_duration(last) {
+            Some(duration) => (rest.trim_end(), Some(duration)),
+            None => (value, None),
+        },
+        None => (value, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2026, 10, 5)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_parse_switch() {
+        assert_eq!(parse_switch("on"), Some((true, None)));
+        assert_eq!(
+            parse_switch("OFF 1h30m"),
+            Some((false, Some(Duration::minutes(90))))
+        );
+        assert_eq!(parse_switch("on later"), None);
+        assert_eq!(parse_switch("maybe"), None);
+        assert_eq!(parse_switch(""), None);
+    }
+
+    #[test]
+    fn test_due_wifi() {
+        let mut timers = Timers::default();
+        timers.set_wifi(Network::Guest, false, at(22));
+        timers.set_wifi(Network::Main, true, at(7));
+
+        assert_eq!(
+            timers.take_due_wifi(at(21)),
+            vec![(Network::Main, true, at(7))]
+        );
+        assert_eq!(timers.take_due_wifi(at(21)), vec![]);
+        assert!(timers.cancel_wifi(Network::Guest));
+        assert_eq!(timers, Timers::default());
+    }
+
+    #[test]
+    fn test_split_duration() {
+        assert_eq!(
+            split_duration("2222 192.168.1.50:22 4h"),
+            ("2222 192.168.1.50:22", Some(Duration::hours(4)))
+        );
+        assert_eq!(
+            split_duration("2222 192.168.1.50:22"),
+            ("2222 192.168.1.50:22", None)
+        );
+    }
+
+    #[test]
+    fn test_due_ports() {
+        let mut timers = Timers::default();
+        timers.set_port(2222, at(18));
+        timers.set_port(8080, at(9));
+
+        assert_eq!(timers.take_due_ports(at(12)), vec![(8080, at(9))]);
+        assert_eq!(timers.port(2222), Some(at(18)));
+        assert_eq!(timers.port(8080), None);
+    }
+
+    #[test]
+    fn test_timers_round_trip() {
+        let mut timers = Timers::default();
+        timers.set_wifi(Network::Guest, false, at(22));
+        let json = serde_json::to_string(&timers).unwrap();
+
+        assert_eq!(serde_json::from_str::<Timers>(&json).unwrap(), timers);
+    }
+}
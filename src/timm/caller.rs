@@ -0,0 +1,316 @@
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+
+// what the modem and phones show when the caller hides their number
+const ANONYMOUS: &[&str] = &[
+    "anonimo",
+    "anonymous",
+    "privato",
+    "private",
+    "riservato",
+    "withheld",
+    "restricted",
+];
+
+const COUNTRIES: &[(&str, &str)] = &[
+    ("+1", "United States/Canada"),
+    ("+30", "Greece"),
+    ("+31", "Netherlands"),
+    ("+32", "Belgium"),
+    ("+33", "France"),
+    ("+34", "Spain"),
+    ("+351", "Portugal"),
+    ("+355", "Albania"),
+    ("+356", "Malta"),
+    ("+378", "San Marino"),
+    ("+379", "Vatican City"),
+    ("+385", "Croatia"),
+    ("+386", "Slovenia"),
+    ("+39", "Italy"),
+    ("+40", "Romania"),
+    ("+41", "Switzerland"),
+    ("+43", "Austria"),
+    ("+44", "United Kingdom"),
+    ("+48", "Poland"),
+    ("+49", "Germany"),
+];
+
+// Italian area codes of the main cities, matched on the longest prefix
+const REGIONS: &[(&str, &str)] = &[
+    ("010", "Genova"),
+    ("011", "Torino"),
+    ("02", "Milano"),
+    ("030", "Brescia"),
+    ("035", "Bergamo"),
+    ("040", "Trieste"),
+    ("041", "Venezia"),
+    ("045", "Verona"),
+    ("049", "Padova"),
+    ("050", "Pisa"),
+    ("051", "Bologna"),
+    ("055", "Firenze"),
+    ("059", "Modena"),
+    ("06", "Roma"),
+    ("070", "Cagliari"),
+    ("071", "Ancona"),
+    ("075", "Perugia"),
+    ("080", "Bari"),
+    ("081", "Napoli"),
+    ("089", "Salerno"),
+    ("090", "Messina"),
+    ("091", "Palermo"),
+    ("095", "Catania"),
+];
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum NumberKind {
+    Mobile,
+    Landline,
+    TollFree,
+    Premium,
+    Unknown,
+}
+
+/// Who the modem says is calling, with numbers kept in E.164 form.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum CallerId {
+    Number(String),
+    Anonymous,
+    Unavailable,
+    Internal(String),
+}
+
+impl CallerId {
+    pub fn number(&self) -> Option<&str> {
+        match self {
+            CallerId::Number(number) => Some(number),
+            _ => None,
+        }
+    }
+
+    /// What contacts and blocklists use to recognise a caller, `CallerId::from` reads it back.
+    pub fn key(&self) -> String {
+        match self {
+            CallerId::Number(number) => number.to_string(),
+            CallerId::Anonymous => "anonymous".to_string(),
+            CallerId::Unavailable => "unavailable".to_string(),
+            CallerId::Internal(extension) => extension.to_string(),
+        }
+    }
+
+    pub fn country(&self) -> Option<&'static str> {
+        let number = self.number()?;
+
+        COUNTRIES
+            .iter()
+            .filter(|(prefix, _)| number.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, country)| *country)
+    }
+
+    pub fn region(&self) -> Option<&'static str> {
+        let national = self.number()?.strip_prefix("+39")?;
+
+        REGIONS
+            .iter()
+            .filter(|(prefix, _)| national.starts_with(prefix))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map(|(_, region)| *region)
+    }
+
+    pub fn kind(&self) -> NumberKind {
+        let national = if let Some(national) = self.number().and_then(|n| n.strip_prefix("+39")) {
+            national
+        } else {
+            return NumberKind::Unknown;
+        };
+
+        if national.starts_with('3') {
+            NumberKind::Mobile
+        } else if national.starts_with('0') {
+            NumberKind::Landline
+        } else if national.starts_with("80") {
+            NumberKind::TollFree
+        } else if national.starts_with("89") {
+            NumberKind::Premium
+        } else {
+            NumberKind::Unknown
+        }
+    }
+}
+
+impl From<&str> for CallerId {
+    fn from(value: &str) -> Self {
+        let value = value.trim();
+        let lowercase = value.to_lowercase();
+
+        if ANONYMOUS.iter().any(|word| lowercase.contains(word)) {
+            return CallerId::Anonymous;
+        }
+
+        let digits: String = value
+            .chars()
+            .filter(|ch| ch.is_ascii_digit() || ['+', '*', '#'].contains(ch))
+            .collect();
+
+        // anything with words in it is a placeholder such as "Sconosciuto" or "Non disponibile"
+        let is_number = !digits.is_empty()
+            && value
+                .chars()
+                .all(|ch| digits.contains(ch) || " -./()".contains(ch));
+
+        if !is_number {
+            debug!("Treating caller {:?} as unavailable", value);
+            CallerId::Unavailable
+        } else if digits.starts_with(['*', '#']) || digits.len() <= 4 {
+            CallerId::Internal(digits)
+        } else {
+            CallerId::Number(e164(&digits))
+        }
+    }
+}
+
+impl From<String> for CallerId {
+    fn from(value: String) -> Self {
+        CallerId::from(value.as_str())
+    }
+}
+
+impl From<CallerId> for String {
+    fn from(value: CallerId) -> Self {
+        value.key()
+    }
+}
+
+impl Display for CallerId {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CallerId::Number(number) => match (self.kind(), self.region(), self.country()) {
+                (NumberKind::Mobile, _, _) => write!(f, "📱 {}", national(number)),
+                (NumberKind::TollFree, _, _) => write!(f, "☎️ {} (toll free)", national(number)),
+                (NumberKind::Premium, _, _) => write!(f, "☎️ {} (premium rate)", national(number)),
+                (_, Some(region), _) => write!(f, "☎️ {} ({})", national(number), region),
+                (_, None, Some("Italy")) => write!(f, "☎️ {}", national(number)),
+                (_, None, Some(country)) => write!(f, "🌍 {} ({})", number, country),
+                (_, None, None) => write!(f, "🌍 {}", number),
+            },
+            CallerId::Anonymous => write!(f, "🕶️ Withheld number"),
+            CallerId::Unavailable => write!(f, "❔ Unknown caller"),
+            CallerId::Internal(extension) => write!(f, "🏠 Extension {}", extension),
+        }
+    }
+}
+
+/// Reads a caller typed in a command: a phone number, or "anonymous" or "unavailable"
+/// for the callers without one. Anything else would end up as an unavailable caller.
+pub fn parse_command(value: &str) -> Option<CallerId> {
+    match value.trim().to_lowercase().as_str() {
+        "anonymous" => Some(CallerId::Anonymous),
+        "unavailable" => Some(CallerId::Unavailable),
+        _ => match CallerId::from(value) {
+            CallerId::Number(number) => Some(CallerId::Number(number)),
+            _ => None,
+        },
+    }
+}
+
+/// Normalises a dialled number to E.164, assuming Italian numbers when there is no prefix.
+pub fn e164(number: &str) -> String {
+    let digits: String = number
+        .chars()
+        .filter(|ch| ch.is_ascii_digit() || *ch == '+')
+        .collect();
+
+    if digits.starts_with('+') {
+        digits
+    } else if let Some(international) = digits.strip_prefix("00") {
+        format!("+{}", international)
+    } else if digits.is_empty() {
+        digits
+    } else {
+        format!("+39{}", digits)
+    }
+}
+
+// Italian numbers are easier to read without the country code
+fn national(number: &str) -> &str {
+    number.strip_prefix("+39").unwrap_or(number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_italian_numbers() {
+        let landline = CallerId::from("06 1234 5678");
+        assert_eq!(landline, CallerId::Number("+390612345678".to_string()));
+        assert_eq!(landline.kind(), NumberKind::Landline);
+        assert_eq!(landline.region(), Some("Roma"));
+        assert_eq!(landline.country(), Some("Italy"));
+        assert_eq!(landline.to_string(), "☎️ 0612345678 (Roma)");
+
+        let mobile = CallerId::from("0039 333-1234567");
+        assert_eq!(mobile, CallerId::Number("+393331234567".to_string()));
+        assert_eq!(mobile.kind(), NumberKind::Mobile);
+        assert_eq!(mobile.region(), None);
+        assert_eq!(mobile.to_string(), "📱 3331234567");
+
+        assert_eq!(CallerId::from("800123456").kind(), NumberKind::TollFree);
+    }
+
+    #[test]
+    fn test_foreign_numbers() {
+        let swiss = CallerId::from("+41 44 123 45 67");
+        assert_eq!(swiss.number(), Some("+41441234567"));
+        assert_eq!(swiss.country(), Some("Switzerland"));
+        assert_eq!(swiss.kind(), NumberKind::Unknown);
+        assert_eq!(swiss.to_string(), "🌍 +41441234567 (Switzerland)");
+
+        assert_eq!(
+            CallerId::from("00378 0549 123456").country(),
+            Some("San Marino")
+        );
+    }
+
+    #[test]
+    fn test_withheld_and_unavailable() {
+        assert_eq!(CallerId::from("Anonimo"), CallerId::Anonymous);
+        assert_eq!(CallerId::from("Numero privato"), CallerId::Anonymous);
+        assert_eq!(CallerId::from(""), CallerId::Unavailable);
+        assert_eq!(CallerId::from("Sconosciuto"), CallerId::Unavailable);
+        assert_eq!(CallerId::from("-"), CallerId::Unavailable);
+    }
+
+    #[test]
+    fn test_internal_extensions() {
+        assert_eq!(CallerId::from("12"), CallerId::Internal("12".to_string()));
+        assert_eq!(
+            CallerId::from("*21#"),
+            CallerId::Internal("*21#".to_string())
+        );
+        assert_eq!(CallerId::from("12").key(), "12");
+    }
+
+    #[test]
+    fn test_parse_command() {
+        assert_eq!(
+            parse_command("06 1234 5678"),
+            Some(CallerId::Number("+390612345678".to_string()))
+        );
+        assert_eq!(parse_command("Anonymous"), Some(CallerId::Anonymous));
+        assert_eq!(parse_command("unavailable"), Some(CallerId::Unavailable));
+        assert_eq!(parse_command("foo"), None);
+        assert_eq!(parse_command("Numero privato"), None);
+        assert_eq!(parse_command("12"), None);
+        assert_eq!(parse_command(""), None);
+    }
+
+    #[test]
+    fn test_e164() {
+        assert_eq!(e164("0418"), "+390418");
+        assert_eq!(e164("+39 06 1234"), "+39061234");
+        assert_eq!(e164("0044 20 1234"), "+44201234");
+    }
+}
@@ -1,17 +1,30 @@
-use chrono::{NaiveDateTime, Utc};
+use super::caller::CallerId;
+use super::layout;
+use super::modem::Modem;
+use chrono::{Local, NaiveDateTime};
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use visdom::Vis;
 
-#[derive(Eq, PartialEq, Debug, Clone)]
+pub const PAGE: &str = "callLog.lp";
+
+// how each firmware and language labels incoming calls
+const INCOMING: &[&str] = &["Ingresso", "In entrata", "Incoming"];
+
+pub(super) const DATE_FORMATS: &[&str] = &["%H:%M:%S - %d:%m:%Y", "%d/%m/%Y %H:%M:%S"];
+
+#[derive(Eq, PartialEq, Debug, Clone, Serialize, Deserialize)]
 pub struct PhoneCall {
-    pub who: String,
+    pub who: CallerId,
     pub when: NaiveDateTime,
+    #[serde(default)]
+    pub missed: bool,
 }
 
 impl PhoneCall {
     pub fn is_today(&self) -> bool {
-        Utc::now()
-            .naive_utc()
+        Local::now()
+            .naive_local()
             .signed_duration_since(self.when)
             .num_days()
             == 0
@@ -20,8 +33,8 @@ impl PhoneCall {
 
 impl PhoneCall {
     pub fn is_recent(&self) -> bool {
-        Utc::now()
-            .naive_utc()
+        Local::now()
+            .naive_local()
             .signed_duration_since(self.when)
             .num_minutes()
             <= 20
@@ -30,18 +43,18 @@ impl PhoneCall {
 
 impl Display for PhoneCall {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        let diff = Utc::now().naive_utc() - self.when;
+        let diff = Local::now().naive_local() - self.when;
         // println!("Phone call was {} minutes ago", diff.num_minutes());
 
         if diff.num_hours() > 1 {
             write!(
                 f,
-                "☎️ {}\n👉 {}",
+                "{}\n👉 {}",
                 self.who,
                 self.when.format("around %l%P on %-d %b")
             )
         } else {
-            write!(f, "☎️ {}", self.who)
+            write!(f, "{}", self.who)
         }
     }
 }
@@ -50,9 +63,24 @@ impl TryFrom<&[String]> for PhoneCall {
     type Error = ();
 
     fn try_from(value: &[String]) -> Result<Self, Self::Error> {
-        let who = value[0].to_string();
-        if let Ok(when) = NaiveDateTime::parse_from_str(&value[3], "%H:%M:%S - %d:%m:%Y") {
-            Ok(PhoneCall { who, when })
+        if value.len() < 4 {
+            warn!("Couldn't parse call from {} cells", value.len());
+            return Err(());
+        }
+
+        let who = CallerId::from(value[0].as_str());
+
+        // the last cell is how long the call lasted, all zeros when nobody answered
+        let missed = value.get(4).is_some_and(|duration| {
+            duration.chars().any(|ch| ch.is_ascii_digit())
+                && duration.chars().all(|ch| !ch.is_ascii_digit() || ch == '0')
+        });
+
+        if let Some(when) = DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(&value[3], format).ok())
+        {
+            Ok(PhoneCall { who, when, missed })
         } else {
             warn!("Couldn't parse date {}", &value[3]);
             Err(())
@@ -60,22 +88,26 @@ impl TryFrom<&[String]> for PhoneCall {
     }
 }
 
-pub async fn download_calls() -> Option<Vec<PhoneCall>> {
-    let resp = reqwest::get("http://192.168.1.1/callLog.lp")
-        .await
-        .ok()?
-        .text()
-        .await
-        .ok()?;
+pub async fn download_calls(modem: &Modem) -> Option<Vec<PhoneCall>> {
+    let resp = modem.page(PAGE).await?;
 
-    let tds = Vis::load(resp)
+    parse_calls(&resp)
+}
+
+pub fn parse_calls(html: &str) -> Option<Vec<PhoneCall>> {
+    if let Some(mismatch) = layout::check_calls(html) {
+        warn!("The {} layout changed: {}", PAGE, mismatch);
+        return None;
+    }
+
+    let tds = Vis::load(html)
         .ok()?
         .find("table.edittable > tr > td.fontSize");
 
     let phone_calls = tds
-        .map(|_index, ele| Vis::dom(ele).text())
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string())
         .chunks_exact(5)
-        .filter(|data| data[2] == "Ingresso")
+        .filter(|data| INCOMING.contains(&data[2].as_str()))
         .filter_map(|data| PhoneCall::try_from(data).ok())
         .collect();
 
@@ -134,6 +166,47 @@ mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
 
+    #[test]
+    fn test_short_row() {
+        let row = vec!["0612345678".to_string(), "".to_string()];
+
+        assert_eq!(PhoneCall::try_from(row.as_slice()), Err(()));
+    }
+
+    #[test]
+    fn test_parse_row() {
+        let row: Vec<String> = [
+            "Anonimo",
+            "",
+            "Ingresso",
+            "09:15:00 - 05:10:2026",
+            "00:00:00",
+        ]
+        .iter()
+        .map(|cell| cell.to_string())
+        .collect();
+
+        assert_eq!(
+            PhoneCall::try_from(row.as_slice()),
+            Ok(PhoneCall {
+                who: CallerId::Anonymous,
+                when: NaiveDateTime::parse_from_str("09:15:00 - 05:10:2026", "%H:%M:%S - %d:%m:%Y")
+                    .unwrap(),
+                missed: true,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_other_date_format() {
+        let row: Vec<String> = ["0612345678", "", "Incoming", "05/10/2026 09:15:00", ""]
+            .iter()
+            .map(|cell| cell.to_string())
+            .collect();
+
+        assert!(PhoneCall::try_from(row.as_slice()).is_ok());
+    }
+
     #[test]
     fn test_no_calls() {
         assert_eq!(get_new_calls(&None, Vec::new()), None);
@@ -142,8 +215,9 @@ mod tests {
     #[test]
     fn test_no_last_call() {
         let new_call: PhoneCall = PhoneCall {
-            who: "new call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000001"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
 
         let calls: Vec<PhoneCall> = vec![new_call];
@@ -154,15 +228,17 @@ mod tests {
     #[test]
     fn test_no_last_return_recent_calls() {
         let new_call: PhoneCall = PhoneCall {
-            who: "new call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000001"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let old_call: PhoneCall = PhoneCall {
-            who: "old call".to_string(),
-            when: Utc::now()
+            who: CallerId::from("0600000002"),
+            when: Local::now()
                 .checked_sub_signed(Duration::seconds(60 * 31))
                 .unwrap()
-                .naive_utc(),
+                .naive_local(),
+            missed: false,
         };
 
         let calls: Vec<PhoneCall> = vec![new_call.clone(), old_call];
@@ -173,8 +249,9 @@ mod tests {
     #[test]
     fn test_no_new_calls() {
         let last_call: PhoneCall = PhoneCall {
-            who: "last call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000003"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
 
         assert_eq!(get_new_calls(&Some(last_call), Vec::new()), None);
@@ -183,17 +260,20 @@ mod tests {
     #[test]
     fn test_last_call_not_found() {
         let last_call: PhoneCall = PhoneCall {
-            who: "last call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000003"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
 
         let new_call_1: PhoneCall = PhoneCall {
-            who: "new call 1".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000004"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let new_call_2: PhoneCall = PhoneCall {
-            who: "new call 2".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000005"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let calls: Vec<PhoneCall> = vec![new_call_1, new_call_2];
 
@@ -203,12 +283,14 @@ mod tests {
     #[test]
     fn test_last_call_is_last_call() {
         let last_call: PhoneCall = PhoneCall {
-            who: "last call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000003"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let old_call: PhoneCall = PhoneCall {
-            who: "old call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000002"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
 
         let calls: Vec<PhoneCall> = vec![last_call.clone(), old_call];
@@ -219,24 +301,29 @@ mod tests {
     #[test]
     fn test_last_call_is_recent_call() {
         let last_call: PhoneCall = PhoneCall {
-            who: "last call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000003"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let new_call_1: PhoneCall = PhoneCall {
-            who: "new call 1".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000004"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let new_call_2: PhoneCall = PhoneCall {
-            who: "new call 2".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000005"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let old_call_1: PhoneCall = PhoneCall {
-            who: "old call 1".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000006"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let old_call_2: PhoneCall = PhoneCall {
-            who: "old call 2".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000007"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
 
         let calls: Vec<PhoneCall> = vec![
@@ -256,12 +343,14 @@ mod tests {
     #[test]
     fn test_last_call_is_oldest_call() {
         let last_call: PhoneCall = PhoneCall {
-            who: "last call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000003"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
         let new_call: PhoneCall = PhoneCall {
-            who: "new call".to_string(),
-            when: Utc::now().naive_utc(),
+            who: CallerId::from("0600000001"),
+            when: Local::now().naive_local(),
+            missed: false,
         };
 
         let calls: Vec<PhoneCall> = vec![new_call.clone(), last_call.clone()];
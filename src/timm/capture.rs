@@ -0,0 +1,109 @@
+use std::collections::HashMap;
+use std::net::Ipv4Addr;
+
+/// The modem pages worth keeping as parser fixtures.
+pub const PAGES: &[&str] = &["callLog.lp", "home.lp"];
+
+// speeds reach 7 digits in kbps, phone numbers are longer
+const MIN_NUMBER_DIGITS: usize = 8;
+
+/// Replaces phone numbers and public IP addresses in a saved page, leaving the markup alone.
+///
+/// The same number is always replaced by the same fake one, keeping its first digits
+/// so that mobiles still look like mobiles and area codes still match.
+pub fn anonymise(html: &str) -> String {
+    let bytes = html.as_bytes();
+    let mut numbers: HashMap<String, String> = HashMap::new();
+    let mut ips: HashMap<String, String> = HashMap::new();
+    let mut anonymised = String::with_capacity(html.len());
+    let mut copied = 0;
+    let mut index = 0;
+
+    while index < bytes.len() {
+        let starts_token = bytes[index].is_ascii_digit()
+            || (bytes[index] == b'+' && bytes.get(index + 1).is_some_and(u8::is_ascii_digit));
+
+        if !starts_token {
+            index += 1;
+            continue;
+        }
+
+        let mut end = index + 1;
+        while end < bytes.len() && (bytes[end].is_ascii_digit() || bytes[end] == b'.') {
+            end += 1;
+        }
+        while bytes[end - 1] == b'.' {
+            end -= 1;
+        }
+
+        let token = &html[index..end];
+        let replacement = if let Ok(ip) = token.parse::<Ipv4Addr>() {
+            (!(ip.is_private() || ip.is_loopback() || ip.is_unspecified())).then(|| {
+                let next = ips.len() + 1;
+                ips.entry(token.to_string())
+                    .or_insert_with(|| format!("192.0.2.{}", next))
+                    .clone()
+            })
+        } else if !token.contains('.') && token.trim_start_matches('+').len() >= MIN_NUMBER_DIGITS {
+            let next = numbers.len() + 1;
+            Some(
+                numbers
+                    .entry(token.to_string())
+                    .or_insert_with(|| fake_number(token, next))
+                    .clone(),
+            )
+        } else {
+            None
+        };
+
+        if let Some(replacement) = replacement {
+            anonymised.push_str(&html[copied..index]);
+            anonymised.push_str(&replacement);
+            copied = end;
+        }
+
+        index = end;
+    }
+
+    anonymised.push_str(&html[copied..]);
+    anonymised
+}
+
+fn fake_number(number: &str, sequence: usize) -> String {
+    let keep = if number.starts_with('+') { 4 } else { 3 };
+    let width = number.len() - keep;
+
+    format!("{}{:0>width$}", &number[..keep], sequence, width = width)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_numbers_are_replaced_consistently() {
+        let html = "<td>0612345678</td><td>+393331234567</td><td>0612345678</td>";
+
+        assert_eq!(
+            anonymise(html),
+            "<td>0610000001</td><td>+393000000002</td><td>0610000001</td>"
+        );
+    }
+
+    #[test]
+    fn test_times_and_speeds_are_kept() {
+        let html = "<td>18:42:10 - 04:10:2026</td><td>00:03:12</td><td>1000000 kbps</td>";
+
+        assert_eq!(anonymise(html), html);
+    }
+
+    #[test]
+    fn test_public_ips_are_replaced() {
+        let html = "<td>79.12.34.56</td><a href=\"http://192.168.1.1/\">79.12.34.56.</a>";
+
+        assert_eq!(
+            anonymise(html),
+            "<td>192.0.2.1</td><a href=\"http://192.168.1.1/\">192.0.2.1.</a>"
+        );
+    }
+}
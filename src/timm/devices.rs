@@ -0,0 +1,132 @@
+use super::modem::Modem;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use visdom::Vis;
+
+pub const PAGE: &str = "connectedDevices.lp";
+
+// Nome host, Indirizzo MAC, Indirizzo IP, Connessione
+const COLUMNS: usize = 4;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Connection {
+    Wired,
+    WiFi,
+}
+
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct Device {
+    pub name: Option<String>,
+    pub mac: String,
+    pub ip: String,
+    pub connection: Connection,
+}
+
+impl Display for Device {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({}, {})",
+            match self.connection {
+                Connection::Wired => "🔌",
+                Connection::WiFi => "📶",
+            },
+            self.name.as_deref().unwrap_or("Unnamed device"),
+            self.mac,
+            self.ip
+        )
+    }
+}
+
+impl TryFrom<&[String]> for Device {
+    type Error = ();
+
+    fn try_from(value: &[String]) -> Result<Self, Self::Error> {
+        if value.len() < COLUMNS {
+            warn!("Couldn't parse device from {} cells", value.len());
+            return Err(());
+        }
+
+        let mac = parse_mac(&value[1]).ok_or_else(|| warn!("Couldn't parse MAC {}", value[1]))?;
+        let connection = if value[3].to_lowercase().contains("wi") {
+            Connection::WiFi
+        } else {
+            Connection::Wired
+        };
+
+        Ok(Device {
+            name: Some(value[0].clone()).filter(|name| !name.is_empty()),
+            mac,
+            ip: value[2].clone(),
+            connection,
+        })
+    }
+}
+
+/// Normalises "A4-83-E7-12-34-56" and "a4:83:e7:12:34:56" to the latter.
+pub fn parse_mac(value: &str) -> Option<String> {
+    let parts: Vec<&str> = value.trim().split([':', '-']).collect();
+
+    if parts.len() != 6
+        || !parts
+            .iter()
+            .all(|part| part.len() == 2 && part.chars().all(|ch| ch.is_ascii_hexdigit()))
+    {
+        return None;
+    }
+
+    Some(parts.join(":").to_lowercase())
+}
+
+pub async fn download_devices(modem: &Modem) -> Option<Vec<Device>> {
+    let resp = modem.page(PAGE).await?;
+
+    parse_devices(&resp)
+}
+
+pub fn parse_devices(html: &str) -> Option<Vec<Device>> {
+    let tds = Vis::load(html)
+        .ok()?
+        .find("table.edittable > tr > td.fontSize");
+
+    let devices = tds
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string())
+        .chunks_exact(COLUMNS)
+        .filter_map(|data| Device::try_from(data).ok())
+        .collect();
+
+    Some(devices)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_mac() {
+        assert_eq!(
+            parse_mac("A4-83-E7-12-34-56"),
+            Some("a4:83:e7:12:34:56".to_string())
+        );
+        assert_eq!(parse_mac("a4:83:e7:12:34"), None);
+        assert_eq!(parse_mac("desktop"), None);
+    }
+
+    #[test]
+    fn test_parse_row() {
+        let data: Vec<String> = vec!["", "F0:9F:C2:AA:BB:CC", "192.168.1.51", "Wi-Fi 2.4GHz"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            Device::try_from(data.as_slice()),
+            Ok(Device {
+                name: None,
+                mac: "f0:9f:c2:aa:bb:cc".to_string(),
+                ip: "192.168.1.51".to_string(),
+                connection: Connection::WiFi,
+            })
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use super::calls::DATE_FORMATS;
+use super::modem::Modem;
+use chrono::NaiveDateTime;
+use std::fmt::{Display, Formatter};
+use visdom::Vis;
+
+pub const PAGE: &str = "eventLog.lp";
+
+// Data e ora, Categoria, Messaggio
+const COLUMNS: usize = 3;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Kind {
+    DslDown,
+    DslUp,
+    PppDown,
+    PppUp,
+    VoipFailure,
+    Other,
+}
+
+impl Kind {
+    /// Guesses what happened from the category and message, in Italian or English.
+    fn from_text(category: &str, message: &str) -> Self {
+        let text = format!("{} {}", category, message).to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|word| text.contains(word));
+
+        if has(&["voip", "sip"]) {
+            if has(&["fallit", "fail", "errore", "error"]) {
+                Kind::VoipFailure
+            } else {
+                Kind::Other
+            }
+        } else if has(&["ppp"]) {
+            // "disconnected" holds "connected", so the ends come first
+            if has(&["terminat", "persa", "down", "disconnect", "lost", "closed"]) {
+                Kind::PppDown
+            } else if has(&["stabilit", "established", "connect", "up"]) {
+                Kind::PppUp
+            } else {
+                Kind::Other
+            }
+        } else if has(&["dsl"]) {
+            // as "non sincronizzata" holds "sincronizzata"
+            if has(&["non ", "not ", "persa", "down", "lost"]) {
+                Kind::DslDown
+            } else if has(&["sincronizzat", "synchroni", "up"]) {
+                Kind::DslUp
+            } else {
+                Kind::Other
+            }
+        } else {
+            Kind::Other
+        }
+    }
+}
+
+/// An entry of the modem's event log.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Event {
+    pub when: NaiveDateTime,
+    pub category: String,
+    pub message: String,
+    pub kind: Kind,
+}
+
+impl Display for Event {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.when.format("%-d %b %H:%M:%S"),
+            self.category,
+            self.message
+        )
+    }
+}
+
+impl TryFrom<&[String]> for Event {
+    type Error = ();
+
+    fn try_from(value: &[String]) -> Result<Self, Self::Error> {
+        if value.len() < COLUMNS {
+            warn!("Couldn't parse event from {} cells", value.len());
+            return Err(());
+        }
+
+        if let Some(when) = DATE_FORMATS
+            .iter()
+            .find_map(|format| NaiveDateTime::parse_from_str(&value[0], format).ok())
+        {
+            Ok(Event {
+                when,
+                category: value[1].clone(),
+                message: value[2].clone(),
+                kind: Kind::from_text(&value[1], &value[2]),
+            })
+        } else {
+            warn!("Couldn't parse date {}", &value[0]);
+            Err(())
+        }
+    }
+}
+
+pub async fn download_events(modem: &Modem) -> Option<Vec<Event>> {
+    let resp = modem.page(PAGE).await?;
+
+    parse_events(&resp)
+}
+
+/// The events, newest first as the modem lists them.
+pub fn parse_events(html: &str) -> Option<Vec<Event>> {
+    let tds = Vis::load(html)
+        .ok()?
+        .find("table.edittable > tr > td.fontSize");
+
+    let events = tds
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string())
+        .chunks_exact(COLUMNS)
+        .filter_map(|data| Event::try_from(data).ok())
+        .collect();
+
+    Some(events)
+}
+
+/// Says what the events explain about a change of IP or speed, such as
+/// "PPP session re-established after DSL resync".
+pub fn explain(events: &[Event]) -> Option<String> {
+    let has = |kinds: &[Kind]| events.iter().any(|event| kinds.contains(&event.kind));
+
+    let dsl = has(&[Kind::DslDown, Kind::DslUp]);
+
+    if has(&[Kind::PppUp]) {
+        if dsl {
+            Some("PPP session re-established after DSL resync".to_string())
+        } else {
+            Some("PPP session re-established".to_string())
+        }
+    } else if has(&[Kind::PppDown]) {
+        Some("PPP session dropped".to_string())
+    } else if dsl {
+        Some("DSL resync".to_string())
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::*;
+
+    fn event(category: &str, message: &str) -> Event {
+        let data: Vec<String> = vec!["03:12:40 - 04:10:2026", category, message]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        Event::try_from(data.as_slice()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_event() {
+        let event = event("PPP", "Sessione PPP stabilita, indirizzo IP 79.12.34.56");
+
+        assert_eq!(
+            event.when,
+            NaiveDate::from_ymd_opt(2026, 10, 4)
+                .unwrap()
+                .and_hms_opt(3, 12, 40)
+                .unwrap()
+        );
+        assert_eq!(event.kind, Kind::PppUp);
+        assert_eq!(
+            event.to_string(),
+            "4 Oct 03:12:40 PPP: Sessione PPP stabilita, indirizzo IP 79.12.34.56"
+        );
+    }
+
+    #[test]
+    fn test_kinds() {
+        assert_eq!(
+            event("DSL", "Linea DSL non sincronizzata").kind,
+            Kind::DslDown
+        );
+        assert_eq!(event("DSL", "Linea DSL sincronizzata").kind, Kind::DslUp);
+        assert_eq!(event("WAN", "PPP session disconnected").kind, Kind::PppDown);
+        assert_eq!(
+            event("VoIP", "Registrazione SIP fallita (403)").kind,
+            Kind::VoipFailure
+        );
+        assert_eq!(event("Sistema", "Accesso utente admin").kind, Kind::Other);
+    }
+
+    #[test]
+    fn test_explain() {
+        let resync = event("DSL", "Linea DSL sincronizzata");
+        let ppp = event("PPP", "Sessione PPP stabilita");
+
+        assert_eq!(
+            explain(&[ppp.clone(), resync.clone()]),
+            Some("PPP session re-established after DSL resync".to_string())
+        );
+        assert_eq!(
+            explain(&[ppp]),
+            Some("PPP session re-established".to_string())
+        );
+        assert_eq!(explain(&[resync]), Some("DSL resync".to_string()));
+        assert_eq!(explain(&[event("Sistema", "Accesso utente admin")]), None);
+    }
+}
@@ -0,0 +1,164 @@
+use super::calls::PhoneCall;
+use super::stats;
+use std::fmt::{Display, Formatter};
+use visdom::Vis;
+
+// Numero, Linea, Tipo, Data e ora, Durata
+const CALL_COLUMNS: usize = 5;
+// IP, download, upload
+const STATS_VALUES: usize = 3;
+
+/// How a page that loaded differs from the pages the parsers were written for,
+/// usually because TIM pushed new firmware.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Mismatch {
+    NoTable,
+    Header(usize),
+    Cells(usize),
+    Unreadable,
+}
+
+impl Display for Mismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Mismatch::NoTable => write!(f, "the table is missing"),
+            Mismatch::Header(columns) => {
+                write!(f, "the header has {} columns instead of 5", columns)
+            }
+            Mismatch::Cells(cells) => write!(f, "{} cells don't fit the expected rows", cells),
+            Mismatch::Unreadable => write!(f, "the values can't be read"),
+        }
+    }
+}
+
+/// Checks that `callLog.lp` still looks like a call log. An empty log is fine.
+pub fn check_calls(html: &str) -> Option<Mismatch> {
+    let page = match Vis::load(html) {
+        Ok(page) => page,
+        Err(_) => return Some(Mismatch::Unreadable),
+    };
+    let table = page.find("table.edittable");
+
+    if table.is_empty() {
+        return Some(Mismatch::NoTable);
+    }
+
+    let columns = table.find("th").length();
+    if columns != CALL_COLUMNS {
+        return Some(Mismatch::Header(columns));
+    }
+
+    let texts = table
+        .find("tr > td.fontSize")
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string());
+    let cells = table.find("td").length();
+
+    if cells != texts.len() || cells % CALL_COLUMNS != 0 {
+        return Some(Mismatch::Cells(cells));
+    }
+
+    let mut rows = texts.chunks_exact(CALL_COLUMNS).peekable();
+    if rows.peek().is_some() && rows.all(|row| PhoneCall::try_from(row).is_err()) {
+        return Some(Mismatch::Unreadable);
+    }
+
+    None
+}
+
+/// Checks that `home.lp` still shows the IP address and the line speeds.
+pub fn check_stats(html: &str) -> Option<Mismatch> {
+    let page = match Vis::load(html) {
+        Ok(page) => page,
+        Err(_) => return Some(Mismatch::Unreadable),
+    };
+    let table = page.find("table.tablecontainttbl");
+
+    if table.is_empty() {
+        return Some(Mismatch::NoTable);
+    }
+
+    let texts = table
+        .find("tr > td.fcolor")
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string());
+
+    if texts.len() < STATS_VALUES {
+        return Some(Mismatch::Cells(texts.len()));
+    }
+
+    // a zero upload while the line resyncs is still the page as expected
+    if stats::parse_int(&texts[1]).is_none() || stats::parse_int(&texts[2]).is_none() {
+        return Some(Mismatch::Unreadable);
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEADER: &str = "<tr><th class=\"fontSize\">Numero</th><th class=\"fontSize\">Linea</th><th class=\"fontSize\">Tipo</th><th class=\"fontSize\">Data e ora</th><th class=\"fontSize\">Durata</th></tr>";
+
+    fn call_log(rows: &str) -> String {
+        format!(
+            "<html><body><table class=\"edittable\">{}{}</table></body></html>",
+            HEADER, rows
+        )
+    }
+
+    #[test]
+    fn test_calls_layout() {
+        let row = "<tr><td class=\"fontSize\">0612345678</td><td class=\"fontSize\">Linea 1</td><td class=\"fontSize\">Ingresso</td><td class=\"fontSize\">18:42:10 - 04:10:2026</td><td class=\"fontSize\">00:03:12</td></tr>";
+
+        assert_eq!(check_calls(&call_log(row)), None);
+        assert_eq!(check_calls(&call_log("")), None);
+    }
+
+    #[test]
+    fn test_calls_layout_changed() {
+        let renamed = "<tr><td class=\"cell\">0612345678</td><td class=\"cell\">Linea 1</td></tr>";
+        let short =
+            "<tr><td class=\"fontSize\">0612345678</td><td class=\"fontSize\">Ingresso</td></tr>";
+        let dates = "<tr><td class=\"fontSize\">0612345678</td><td class=\"fontSize\">Linea 1</td><td class=\"fontSize\">Ingresso</td><td class=\"fontSize\">2026-10-04T18:42:10</td><td class=\"fontSize\">00:03:12</td></tr>";
+
+        assert_eq!(
+            check_calls("<html><body><table class=\"calls\"></table></body></html>"),
+            Some(Mismatch::NoTable)
+        );
+        assert_eq!(
+            check_calls("<table class=\"edittable\"><tr><th>Numero</th></tr></table>"),
+            Some(Mismatch::Header(1))
+        );
+        assert_eq!(check_calls(&call_log(renamed)), Some(Mismatch::Cells(2)));
+        assert_eq!(check_calls(&call_log(short)), Some(Mismatch::Cells(2)));
+        assert_eq!(check_calls(&call_log(dates)), Some(Mismatch::Unreadable));
+    }
+
+    #[test]
+    fn test_stats_layout_changed() {
+        let home = |cells: &str| {
+            format!(
+                "<html><body><table class=\"tablecontainttbl\">{}</table></body></html>",
+                cells
+            )
+        };
+
+        assert_eq!(
+            check_stats(&home("<tr><td class=\"fcolor\">1.2.3.4</td></tr><tr><td class=\"fcolor\">100 kbps</td></tr><tr><td class=\"fcolor\">10 kbps</td></tr>")),
+            None
+        );
+        assert_eq!(
+            check_stats(&home("<tr><td class=\"fcolor\">1.2.3.4</td></tr><tr><td class=\"fcolor\">0 kbps</td></tr><tr><td class=\"fcolor\">0 kbps</td></tr>")),
+            None
+        );
+        assert_eq!(check_stats("<html></html>"), Some(Mismatch::NoTable));
+        assert_eq!(
+            check_stats(&home("<tr><td class=\"fcolor\">1.2.3.4</td></tr>")),
+            Some(Mismatch::Cells(1))
+        );
+        assert_eq!(
+            check_stats(&home("<tr><td class=\"fcolor\">1.2.3.4</td></tr><tr><td class=\"fcolor\">100 Mbps</td></tr><tr><td class=\"fcolor\">fast</td></tr>")),
+            Some(Mismatch::Unreadable)
+        );
+    }
+}
@@ -1,3 +1,12 @@
+pub mod caller;
 pub mod calls;
+pub mod capture;
+pub mod devices;
+pub mod events;
+pub mod layout;
+pub mod modem;
+pub mod nat;
 pub mod stats;
 pub mod tools;
+pub mod voip;
+pub mod wifi;
@@ -0,0 +1,71 @@
+use std::env;
+use std::time::Duration;
+
+/// Where the TIM modem answers, and how long to wait for it.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Modem {
+    pub url: String,
+    pub timeout: Duration,
+}
+
+impl Default for Modem {
+    fn default() -> Self {
+        Modem {
+            url: "http://192.168.1.1".to_string(),
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+impl Modem {
+    pub fn new(url: &str) -> Self {
+        Modem {
+            url: url.trim_end_matches('/').to_string(),
+            ..Modem::default()
+        }
+    }
+
+    /// Reads `MODEM_URL` and `MODEM_TIMEOUT` in seconds.
+    pub fn from_env() -> Self {
+        let mut modem = match env::var("MODEM_URL") {
+            Ok(url) => Modem::new(&url),
+            Err(_) => Modem::default(),
+        };
+
+        if let Some(timeout) = env::var("MODEM_TIMEOUT")
+            .ok()
+            .and_then(|timeout| timeout.parse().ok())
+        {
+            modem.timeout = Duration::from_secs(timeout);
+        }
+
+        modem
+    }
+
+    pub fn page_url(&self, page: &str) -> String {
+        format!("{}/{}", self.url, page)
+    }
+
+    pub fn client(&self) -> reqwest::Client {
+        reqwest::Client::builder()
+            .timeout(self.timeout)
+            .build()
+            .unwrap_or_default()
+    }
+
+    pub async fn get(&self, page: &str) -> Option<reqwest::Response> {
+        let resp = self.client().get(self.page_url(page)).send().await;
+
+        match resp.and_then(|resp| resp.error_for_status()) {
+            Ok(resp) => Some(resp),
+            Err(err) => {
+                debug!("Couldn't get {}: {}", page, err);
+                None
+            }
+        }
+    }
+
+    pub async fn page(&self, page: &str) -> Option<String> {
+        self.get(page).await?.text().await.ok()
+    }
+}
@@ -0,0 +1,218 @@
+use super::modem::Modem;
+use super::tools;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::net::Ipv4Addr;
+use visdom::Vis;
+
+pub const PAGE: &str = "portForwarding.lp";
+
+// Nome, Protocollo, Porta esterna, Indirizzo IP, Porta interna
+const COLUMNS: usize = 5;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Protocol {
+    Tcp,
+    Udp,
+    Both,
+}
+
+impl Protocol {
+    fn form_value(&self) -> &'static str {
+        match self {
+            Protocol::Tcp => "TCP",
+            Protocol::Udp => "UDP",
+            Protocol::Both => "TCP/UDP",
+        }
+    }
+}
+
+impl TryFrom<&str> for Protocol {
+    type Error = ();
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.trim().to_uppercase().as_str() {
+            "TCP" => Ok(Protocol::Tcp),
+            "UDP" => Ok(Protocol::Udp),
+            "TCP/UDP" | "TCP+UDP" | "ENTRAMBI" | "BOTH" => Ok(Protocol::Both),
+            _ => Err(()),
+        }
+    }
+}
+
+/// A port-forwarding rule, sending connections on a public port to a device on the LAN.
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
+pub struct Forward {
+    pub name: String,
+    pub protocol: Protocol,
+    pub external: u16,
+    pub ip: Ipv4Addr,
+    pub internal: u16,
+}
+
+impl Display for Forward {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "🔓 {} {} → {}:{} ({})",
+            self.external,
+            self.protocol.form_value(),
+            self.ip,
+            self.internal,
+            self.name
+        )
+    }
+}
+
+impl TryFrom<&[String]> for Forward {
+    type Error = ();
+
+    fn try_from(value: &[String]) -> Result<Self, Self::Error> {
+        if value.len() < COLUMNS {
+            warn!("Couldn't parse port forwarding from {} cells", value.len());
+            return Err(());
+        }
+
+        parse_row(value).ok_or_else(|| warn!("Couldn't parse port forwarding {:?}", value))
+    }
+}
+
+fn parse_row(value: &[String]) -> Option<Forward> {
+    Some(Forward {
+        name: value[0].clone(),
+        protocol: Protocol::try_from(value[1].as_str()).ok()?,
+        external: value[2].parse().ok()?,
+        ip: value[3].parse().ok()?,
+        internal: value[4].parse().ok()?,
+    })
+}
+
+impl TryFrom<&str> for Forward {
+    type Error = ();
+
+    /// Reads rules such as "2222 192.168.1.50:22" or "5060/udp 192.168.1.60:5060".
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut words = value.split_whitespace();
+        let (external, target) = (words.next().ok_or(())?, words.next().ok_or(())?);
+
+        if words.next().is_some() {
+            return Err(());
+        }
+
+        let (external, protocol) = match external.split_once('/') {
+            Some((port, protocol)) => (port, Protocol::try_from(protocol)?),
+            None => (external, Protocol::Tcp),
+        };
+        let external: u16 = external.parse().map_err(|_| ())?;
+        let (ip, internal) = match target.split_once(':') {
+            Some((ip, port)) => (ip, port.parse().map_err(|_| ())?),
+            None => (target, external),
+        };
+
+        Ok(Forward {
+            name: format!("callog-{}", external),
+            protocol,
+            external,
+            ip: ip.parse().map_err(|_| ())?,
+            internal,
+        })
+    }
+}
+
+pub async fn download_forwards(modem: &Modem) -> Option<Vec<Forward>> {
+    let resp = modem.page(PAGE).await?;
+
+    parse_forwards(&resp)
+}
+
+pub fn parse_forwards(html: &str) -> Option<Vec<Forward>> {
+    let tds = Vis::load(html)
+        .ok()?
+        .find("table.edittable > tr > td.fontSize");
+
+    let forwards = tds
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string())
+        .chunks_exact(COLUMNS)
+        .filter_map(|data| Forward::try_from(data).ok())
+        .collect();
+
+    Some(forwards)
+}
+
+pub async fn add_forward(modem: &Modem, forward: &Forward) -> Option<()> {
+    let external = forward.external.to_string();
+    let ip = forward.ip.to_string();
+    let internal = forward.internal.to_string();
+
+    tools::post_form(
+        modem,
+        PAGE,
+        &[
+            ("action", "addRule"),
+            ("name", &forward.name),
+            ("protocol", forward.protocol.form_value()),
+            ("external", &external),
+            ("ip", &ip),
+            ("internal", &internal),
+        ],
+    )
+    .await?
+    .error_for_status()
+    .ok()
+    .map(|_| ())
+}
+
+/// Removes the rule for a public port.
+pub async fn remove_forward(modem: &Modem, external: u16) -> Option<()> {
+    let external = external.to_string();
+
+    tools::post_form(
+        modem,
+        PAGE,
+        &[("action", "deleteRule"), ("external", &external)],
+    )
+    .await?
+    .error_for_status()
+    .ok()
+    .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rule() {
+        assert_eq!(
+            Forward::try_from("2222 192.168.1.50:22"),
+            Ok(Forward {
+                name: "callog-2222".to_string(),
+                protocol: Protocol::Tcp,
+                external: 2222,
+                ip: Ipv4Addr::new(192, 168, 1, 50),
+                internal: 22,
+            })
+        );
+        assert_eq!(
+            Forward::try_from("5060/udp 192.168.1.60")
+                .map(|forward| (forward.protocol, forward.internal)),
+            Ok((Protocol::Udp, 5060))
+        );
+        assert!(Forward::try_from("2222").is_err());
+        assert!(Forward::try_from("2222 nas:22").is_err());
+        assert!(Forward::try_from("99999 192.168.1.50:22").is_err());
+    }
+
+    #[test]
+    fn test_parse_row() {
+        let data: Vec<String> = vec!["nas", "TCP", "8443", "192.168.1.10", "443"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+
+        assert_eq!(
+            Forward::try_from(data.as_slice()).map(|forward| forward.to_string()),
+            Ok("🔓 8443 TCP → 192.168.1.10:443 (nas)".to_string())
+        );
+    }
+}
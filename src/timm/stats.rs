@@ -1,14 +1,19 @@
+use super::layout;
+use super::modem::Modem;
+use serde::{Deserialize, Serialize};
 use std::fmt::{Display, Formatter};
 use visdom::Vis;
 
-#[derive(PartialEq, Eq, Debug)]
+pub const PAGE: &str = "home.lp";
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum LineSpeed {
     Bad,
     Slow,
     Normal,
 }
 
-#[derive(PartialEq, Eq, Debug)]
+#[derive(PartialEq, Eq, Debug, Clone, Serialize, Deserialize)]
 pub struct LineStats {
     pub ip: String,
     pub upload: u32,
@@ -40,7 +45,7 @@ impl Display for LineSpeed {
     }
 }
 
-fn parse_int(input: &str) -> Option<u32> {
+pub(super) fn parse_int(input: &str) -> Option<u32> {
     input
         .chars()
         .skip_while(|ch| !ch.is_ascii_digit())
@@ -99,25 +104,31 @@ impl TryFrom<Vec<String>> for LineStats {
     }
 }
 
-pub async fn download_stats() -> Option<LineStats> {
-    let home_resp = reqwest::get("http://192.168.1.1/home.lp")
-        .await
-        .ok()?
-        .text()
-        .await
-        .ok()?;
+pub async fn download_stats(modem: &Modem) -> Option<LineStats> {
+    let home_resp = modem.page(PAGE).await?;
+
+    parse_stats(&home_resp)
+}
 
-    let tds = Vis::load(home_resp)
+pub fn parse_stats(html: &str) -> Option<LineStats> {
+    if let Some(mismatch) = layout::check_stats(html) {
+        warn!("The {} layout changed: {}", PAGE, mismatch);
+        return None;
+    }
+
+    let tds = Vis::load(html)
         .ok()?
         .find("table.tablecontainttbl > tr > td.fcolor");
     debug!("There are {} matching cells.", tds.length());
 
-    let texts = tds.map(|_index, ele| Vis::dom(ele).text());
+    let texts = tds.map(|_index, ele| Vis::dom(ele).text().trim().to_string());
 
     // check the external IP and download/upload speeds
-    debug!("IP: {}", texts[0]);
-    debug!("Download: {}", texts[1]);
-    debug!("Upload: {}", texts[2]);
+    if texts.len() >= 3 {
+        debug!("IP: {}", texts[0]);
+        debug!("Download: {}", texts[1]);
+        debug!("Upload: {}", texts[2]);
+    }
 
     LineStats::try_from(texts).ok()
 
@@ -135,6 +146,7 @@ pub async fn download_stats() -> Option<LineStats> {
 }
 
 #[cfg(test)]
+#[allow(clippy::bool_assert_comparison)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
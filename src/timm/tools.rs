@@ -1,26 +1,31 @@
+use super::modem::Modem;
 use std::collections::HashMap;
 
-pub async fn reboot() -> Option<reqwest::Response> {
-    let tool_resp = reqwest::get("http://192.168.1.1/tool.lp").await.ok()?;
+/// Posts a settings form, with the `rn` token the modem hands out on `tool.lp`.
+pub async fn post_form(
+    modem: &Modem,
+    page: &str,
+    fields: &[(&str, &str)],
+) -> Option<reqwest::Response> {
+    let tool_resp = modem.get("tool.lp").await?;
     let mut cookies = tool_resp.cookies();
 
-    let client = reqwest::Client::new();
+    let client = modem.client();
 
     if let Some(cookie) = cookies.next() {
-        let mut params = HashMap::new();
-        params.insert("action", "saveRestart");
+        let mut params: HashMap<&str, &str> = fields.iter().copied().collect();
         params.insert("rn", cookie.value());
 
-        let post_res = client
-            .post("http://192.168.1.1/resetAG.lp")
-            .form(&params)
-            .send()
-            .await;
+        let post_res = client.post(modem.page_url(page)).form(&params).send().await;
 
-        debug!("Reboot response: {:?}", post_res);
+        debug!("{} response: {:?}", page, post_res);
 
         return post_res.ok();
     }
 
     None
 }
+
+pub async fn reboot(modem: &Modem) -> Option<reqwest::Response> {
+    post_form(modem, "resetAG.lp", &[("action", "saveRestart")]).await
+}
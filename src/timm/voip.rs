@@ -0,0 +1,157 @@
+use super::modem::Modem;
+use std::fmt::{Display, Formatter};
+use visdom::Vis;
+
+pub const PAGE: &str = "voipStatus.lp";
+
+// Linea, Numero, Stato
+const COLUMNS: usize = 3;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum Registration {
+    Registered,
+    Unregistered,
+    /// The line isn't set up, so there's nothing to register.
+    Unconfigured,
+}
+
+impl From<&str> for Registration {
+    fn from(value: &str) -> Self {
+        let value = value.to_lowercase();
+        let has = |words: &[&str]| words.iter().any(|word| value.contains(word));
+
+        // "non registrata" holds "registrata", so the failures come first
+        if has(&["configurat", "configured", "disabilitat", "disabled"]) {
+            Registration::Unconfigured
+        } else if has(&[
+            "non ",
+            "not ",
+            "unregistered",
+            "fail",
+            "fallit",
+            "errore",
+            "error",
+        ]) {
+            Registration::Unregistered
+        } else if has(&["registrat", "registered"]) {
+            Registration::Registered
+        } else {
+            Registration::Unregistered
+        }
+    }
+}
+
+/// A phone line of the modem and whether it's registered with the SIP server.
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Line {
+    pub name: String,
+    pub number: Option<String>,
+    pub registration: Registration,
+}
+
+impl Line {
+    pub fn is_registered(&self) -> bool {
+        self.registration == Registration::Registered
+    }
+
+    pub fn is_configured(&self) -> bool {
+        self.registration != Registration::Unconfigured
+    }
+
+    /// The line as named in alerts, with its number when it has one.
+    pub fn label(&self) -> String {
+        match &self.number {
+            Some(number) => format!("{} ({})", self.name, number),
+            None => self.name.clone(),
+        }
+    }
+}
+
+impl Display for Line {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self.registration {
+            Registration::Registered => write!(f, "☎️ {} registered", self.label()),
+            Registration::Unregistered => write!(f, "❌ {} not registered", self.label()),
+            Registration::Unconfigured => write!(f, "➖ {} not configured", self.label()),
+        }
+    }
+}
+
+impl TryFrom<&[String]> for Line {
+    type Error = ();
+
+    fn try_from(value: &[String]) -> Result<Self, Self::Error> {
+        if value.len() < COLUMNS || value[0].is_empty() {
+            warn!("Couldn't parse phone line {:?}", value);
+            return Err(());
+        }
+
+        Ok(Line {
+            name: value[0].clone(),
+            number: Some(value[1].clone()).filter(|number| !number.is_empty()),
+            registration: Registration::from(value[2].as_str()),
+        })
+    }
+}
+
+pub async fn download_lines(modem: &Modem) -> Option<Vec<Line>> {
+    let resp = modem.page(PAGE).await?;
+
+    parse_lines(&resp)
+}
+
+pub fn parse_lines(html: &str) -> Option<Vec<Line>> {
+    let tds = Vis::load(html)
+        .ok()?
+        .find("table.edittable > tr > td.fontSize");
+
+    let lines = tds
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string())
+        .chunks_exact(COLUMNS)
+        .filter_map(|data| Line::try_from(data).ok())
+        .collect();
+
+    Some(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(cells: &[&str]) -> Result<Line, ()> {
+        let data: Vec<String> = cells.iter().map(|cell| cell.to_string()).collect();
+
+        Line::try_from(data.as_slice())
+    }
+
+    #[test]
+    fn test_registration() {
+        assert_eq!(Registration::from("Registrata"), Registration::Registered);
+        assert_eq!(Registration::from("Registered"), Registration::Registered);
+        assert_eq!(
+            Registration::from("Non registrata"),
+            Registration::Unregistered
+        );
+        assert_eq!(
+            Registration::from("Registrazione in corso"),
+            Registration::Unregistered
+        );
+        assert_eq!(
+            Registration::from("Non configurata"),
+            Registration::Unconfigured
+        );
+    }
+
+    #[test]
+    fn test_parse_line() {
+        assert_eq!(
+            line(&["Linea 1", "0612345678", "Non registrata"]).map(|line| line.to_string()),
+            Ok("❌ Linea 1 (0612345678) not registered".to_string())
+        );
+        assert_eq!(
+            line(&["Linea 2", "", "Non configurata"]).map(|line| line.number),
+            Ok(None)
+        );
+        assert!(line(&["Linea 1", "0612345678"]).is_err());
+    }
+}
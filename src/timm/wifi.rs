@@ -0,0 +1,151 @@
+use super::modem::Modem;
+use super::tools;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use visdom::Vis;
+
+const ENABLED: &[&str] = &["Attivo", "Abilitato", "Active", "Enabled", "On"];
+
+#[derive(PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Network {
+    Main,
+    Guest,
+}
+
+impl Network {
+    pub fn page(&self) -> &'static str {
+        match self {
+            Network::Main => "wifi.lp",
+            Network::Guest => "guestWifi.lp",
+        }
+    }
+}
+
+impl Display for Network {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Network::Main => "Wi-Fi",
+                Network::Guest => "Guest Wi-Fi",
+            }
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct WifiStatus {
+    pub network: Network,
+    pub enabled: bool,
+    pub ssid: String,
+    pub channel: String,
+    pub clients: u32,
+}
+
+impl Display for WifiStatus {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        if self.enabled {
+            write!(
+                f,
+                "📶 {} on: {}, channel {}, {} clients",
+                self.network, self.ssid, self.channel, self.clients
+            )
+        } else {
+            write!(f, "📴 {} off: {}", self.network, self.ssid)
+        }
+    }
+}
+
+impl TryFrom<(Network, Vec<String>)> for WifiStatus {
+    type Error = ();
+
+    fn try_from((network, value): (Network, Vec<String>)) -> Result<Self, Self::Error> {
+        if value.len() < 4 {
+            warn!(
+                "Couldn't parse {} status from {} cells",
+                network,
+                value.len()
+            );
+            return Err(());
+        }
+
+        Ok(WifiStatus {
+            network,
+            enabled: ENABLED.contains(&value[0].as_str()),
+            ssid: value[1].clone(),
+            channel: value[2].clone(),
+            clients: value[3].parse().map_err(|_| {
+                warn!("Couldn't parse clients {}", value[3]);
+            })?,
+        })
+    }
+}
+
+pub async fn download_status(modem: &Modem, network: Network) -> Option<WifiStatus> {
+    let resp = modem.page(network.page()).await?;
+
+    parse_status(network, &resp)
+}
+
+pub fn parse_status(network: Network, html: &str) -> Option<WifiStatus> {
+    let texts = Vis::load(html)
+        .ok()?
+        .find("table.tablecontainttbl > tr > td.fcolor")
+        .map(|_index, ele| Vis::dom(ele).text().trim().to_string());
+
+    WifiStatus::try_from((network, texts)).ok()
+}
+
+/// Switches a network on or off.
+pub async fn set_enabled(modem: &Modem, network: Network, enabled: bool) -> Option<()> {
+    let enabled = if enabled { "1" } else { "0" };
+
+    tools::post_form(
+        modem,
+        network.page(),
+        &[("action", "saveWifi"), ("enabled", enabled)],
+    )
+    .await?
+    .error_for_status()
+    .ok()
+    .map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(cells: &[&str]) -> Vec<String> {
+        cells.iter().map(|cell| cell.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_status() {
+        let status =
+            WifiStatus::try_from((Network::Guest, cells(&["Attivo", "TIM-Ospiti", "11", "2"])));
+
+        assert_eq!(
+            status,
+            Ok(WifiStatus {
+                network: Network::Guest,
+                enabled: true,
+                ssid: "TIM-Ospiti".to_string(),
+                channel: "11".to_string(),
+                clients: 2,
+            })
+        );
+        assert_eq!(
+            status.unwrap().to_string(),
+            "📶 Guest Wi-Fi on: TIM-Ospiti, channel 11, 2 clients"
+        );
+    }
+
+    #[test]
+    fn test_bad_status() {
+        assert!(WifiStatus::try_from((Network::Main, cells(&["Attivo", "TIM"]))).is_err());
+        assert!(
+            WifiStatus::try_from((Network::Main, cells(&["Attivo", "TIM", "6", "many"]))).is_err()
+        );
+    }
+}
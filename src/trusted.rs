@@ -0,0 +1,120 @@
+use crate::store;
+use crate::timm::devices::{self, Device};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+const FILE: &str = "devices.json";
+
+/// The devices labelled with `/trust`, and every MAC address seen on the network.
+#[derive(Serialize, Deserialize, Default, PartialEq, Eq, Debug)]
+pub struct TrustedDevices {
+    names: BTreeMap<String, String>,
+    #[serde(default)]
+    seen: BTreeSet<String>,
+    // whether the devices on the network were learnt, which /trust alone doesn't do
+    #[serde(default)]
+    learned: bool,
+}
+
+impl TrustedDevices {
+    pub fn load() -> Self {
+        let mut trusted: TrustedDevices = store::load(FILE);
+
+        // earlier versions didn't say, but only looking at the network sees untrusted devices
+        if trusted
+            .seen
+            .iter()
+            .any(|mac| !trusted.names.contains_key(mac))
+        {
+            trusted.learned = true;
+        }
+
+        trusted
+    }
+
+    pub fn save(&self) -> Option<()> {
+        store::save(FILE, self)
+    }
+
+    pub fn name(&self, mac: &str) -> Option<&str> {
+        self.names.get(&key(mac)).map(String::as_str)
+    }
+
+    pub fn trust(&mut self, mac: &str, name: &str) -> Option<()> {
+        let mac = devices::parse_mac(mac)?;
+
+        self.seen.insert(mac.clone());
+        self.names.insert(mac, name.trim().to_string());
+        Some(())
+    }
+
+    pub fn is_trusted(&self, mac: &str) -> bool {
+        self.names.contains_key(&key(mac))
+    }
+
+    /// Remembers the devices, returning the ones never seen before that aren't trusted.
+    pub fn see<'a>(&mut self, devices: &'a [Device]) -> Vec<&'a Device> {
+        self.learned = true;
+
+        devices
+            .iter()
+            .filter(|device| self.seen.insert(key(&device.mac)) && !self.is_trusted(&device.mac))
+            .collect()
+    }
+
+    /// Whether the devices on the network have never been looked at.
+    pub fn is_learning(&self) -> bool {
+        !self.learned
+    }
+
+    /// The device as labelled, or as the modem names it.
+    pub fn describe(&self, device: &Device) -> String {
+        match self.name(&device.mac) {
+            Some(name) => format!("{}\n✅ {}", device, name),
+            None => device.to_string(),
+        }
+    }
+}
+
+fn key(mac: &str) -> String {
+    devices::parse_mac(mac).unwrap_or_else(|| mac.to_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::timm::devices::Connection;
+
+    use super::*;
+
+    fn device(mac: &str) -> Device {
+        Device {
+            name: None,
+            mac: mac.to_string(),
+            ip: "192.168.1.2".to_string(),
+            connection: Connection::WiFi,
+        }
+    }
+
+    #[test]
+    fn test_new_devices_are_reported_once() {
+        let mut trusted = TrustedDevices::default();
+        trusted.trust("00-1A-2B-3C-4D-5E", "Desktop").unwrap();
+
+        let devices = vec![device("00:1a:2b:3c:4d:5e"), device("f0:9f:c2:aa:bb:cc")];
+
+        assert_eq!(trusted.see(&devices), vec![&devices[1]]);
+        assert!(trusted.see(&devices).is_empty());
+        assert_eq!(trusted.name("00:1A:2B:3C:4D:5E"), Some("Desktop"));
+        assert_eq!(trusted.trust("desktop", "Desktop"), None);
+    }
+
+    #[test]
+    fn test_trusting_first_still_learns() {
+        let mut trusted = TrustedDevices::default();
+        trusted.trust("00-1A-2B-3C-4D-5E", "Desktop").unwrap();
+
+        assert!(trusted.is_learning());
+        trusted.see(&[device("f0:9f:c2:aa:bb:cc")]);
+        assert!(!trusted.is_learning());
+    }
+}
@@ -0,0 +1,68 @@
+mod common;
+
+use common::modem::MockModem;
+use std::process::{Command, Output};
+
+/// Runs the `callog` binary against the mock modem.
+async fn callog(modem: &MockModem, args: &[&str]) -> Output {
+    let url = modem.url.clone();
+    let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+    tokio::task::spawn_blocking(move || {
+        Command::new(env!("CARGO_BIN_EXE_callog"))
+            .args(args)
+            .env("MODEM_URL", url)
+            .output()
+            .unwrap()
+    })
+    .await
+    .unwrap()
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[tokio::test]
+async fn test_calls_as_json_and_csv() {
+    let modem = MockModem::start().await;
+
+    let output = callog(&modem, &["calls", "--json"]).await;
+    assert!(output.status.success());
+
+    let json: serde_json::Value = serde_json::from_str(&stdout(&output)).unwrap();
+    let phone_calls = json.as_array().unwrap();
+    assert_eq!(phone_calls.len(), 3);
+    // oldest first
+    assert_eq!(phone_calls[2]["number"], "+390612345678");
+
+    let output = callog(&modem, &["calls", "--csv"]).await;
+    let csv = stdout(&output);
+    let rows: Vec<&str> = csv.lines().collect();
+    assert_eq!(rows[0], "when,number,name,missed");
+    assert_eq!(rows.len(), 4);
+    assert!(rows[2].ends_with(",anonymous,,true"));
+
+    let output = callog(&modem, &["calls", "--ics"]).await;
+    assert_eq!(stdout(&output).matches("BEGIN:VEVENT").count(), 3);
+}
+
+#[tokio::test]
+async fn test_stats_and_reboot() {
+    let modem = MockModem::start().await;
+
+    let output = callog(&modem, &["stats", "--csv"]).await;
+    assert!(output.status.success());
+    assert!(stdout(&output).contains("79.12.34.56"));
+
+    let output = callog(&modem, &["reboot"]).await;
+    assert!(!output.status.success());
+    assert_eq!(modem.reboots(), 0);
+
+    let output = callog(&modem, &["reboot", "--yes"]).await;
+    assert!(output.status.success());
+    assert_eq!(modem.reboots(), 1);
+
+    let output = callog(&modem, &["calls", "--since", "soon"]).await;
+    assert!(!output.status.success());
+}
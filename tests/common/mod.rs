@@ -0,0 +1,258 @@
+#![allow(dead_code)]
+
+pub mod modem;
+pub mod telegram;
+
+use std::collections::HashMap;
+use std::env;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tempfile::TempDir;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::sleep;
+
+// the bot finds its state through DATA_DIR, which every test in the process shares
+static DATA_DIR: Mutex<()> = Mutex::new(());
+
+/// A data directory of the test's own, removed when it's dropped.
+pub struct DataDir {
+    // dropped in order, so the directory is gone before the next test gets its own
+    _dir: TempDir,
+    _lock: MutexGuard<'static, ()>,
+}
+
+/// Keeps the state the bot saves away from the real data directory and from other tests,
+/// which wait for this one to drop it.
+pub fn init() -> DataDir {
+    // a test that failed holding the lock hasn't left anything behind for the others
+    let lock = DATA_DIR.lock().unwrap_or_else(PoisonError::into_inner);
+    let dir = tempfile::Builder::new()
+        .prefix("callog_bot-test-")
+        .tempdir()
+        .unwrap();
+    env::set_var("DATA_DIR", dir.path());
+
+    DataDir {
+        _dir: dir,
+        _lock: lock,
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    pub fn page(&self) -> &str {
+        self.path
+            .trim_start_matches('/')
+            .split('?')
+            .next()
+            .unwrap_or_default()
+    }
+
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).to_string()
+    }
+
+    /// The fields of a URL-encoded form body.
+    pub fn form(&self) -> HashMap<String, String> {
+        self.text()
+            .split('&')
+            .filter_map(|field| field.split_once('='))
+            .map(|(name, value)| (decode(name), decode(value)))
+            .collect()
+    }
+}
+
+fn decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::new();
+    let mut index = 0;
+
+    while index < bytes.len() {
+        match bytes[index] {
+            b'+' => decoded.push(b' '),
+            b'%' if index + 2 < bytes.len() => {
+                let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).unwrap_or("");
+                decoded.push(u8::from_str_radix(hex, 16).unwrap_or(b'?'));
+                index += 2;
+            }
+            byte => decoded.push(byte),
+        }
+        index += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+#[derive(Debug, Clone)]
+pub struct Response {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+    pub delay: Duration,
+}
+
+impl Response {
+    pub fn new(status: u16, body: &str) -> Self {
+        Response {
+            status,
+            headers: Vec::new(),
+            body: body.as_bytes().to_vec(),
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+}
+
+/// Serves HTTP/1.1 on a free local port, closing the connection when `handler` returns nothing.
+pub async fn serve<F>(handler: F) -> SocketAddr
+where
+    F: Fn(Request) -> Option<Response> + Send + Sync + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let handler = Arc::new(handler);
+
+    tokio::spawn(async move {
+        while let Ok((stream, _)) = listener.accept().await {
+            let handler = handler.clone();
+
+            tokio::spawn(async move {
+                handle(stream, handler.as_ref()).await;
+            });
+        }
+    });
+
+    addr
+}
+
+async fn handle<F>(mut stream: TcpStream, handler: &F)
+where
+    F: Fn(Request) -> Option<Response>,
+{
+    let request = match read_request(&mut stream).await {
+        Some(request) => request,
+        None => return,
+    };
+
+    let response = match handler(request) {
+        Some(response) => response,
+        None => return,
+    };
+
+    sleep(response.delay).await;
+
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\nContent-Length: {}\r\nConnection: close\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+
+    let _ = stream.write_all(head.as_bytes()).await;
+    let _ = stream.write_all(&response.body).await;
+    let _ = stream.shutdown().await;
+}
+
+fn dechunk(data: &[u8]) -> Vec<u8> {
+    let mut body = Vec::new();
+    let mut rest = data;
+
+    while let Some(line_end) = rest.windows(2).position(|window| window == b"\r\n") {
+        let size = String::from_utf8_lossy(&rest[..line_end]);
+        let size = usize::from_str_radix(size.trim(), 16).unwrap_or(0);
+        if size == 0 || rest.len() < line_end + 2 + size {
+            break;
+        }
+
+        body.extend_from_slice(&rest[line_end + 2..line_end + 2 + size]);
+        rest = &rest[(line_end + 4 + size).min(rest.len())..];
+    }
+
+    body
+}
+
+async fn read_request(stream: &mut TcpStream) -> Option<Request> {
+    let mut data = Vec::new();
+    let mut buffer = [0; 4096];
+
+    let head_end = loop {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        data.extend_from_slice(&buffer[..read]);
+
+        if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+            break end + 4;
+        }
+    };
+
+    let head = String::from_utf8_lossy(&data[..head_end]).to_string();
+    let mut lines = head.lines();
+    let mut request_line = lines.next()?.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let headers: HashMap<String, String> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+
+    let length: usize = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+
+    let mut body = data[head_end..].to_vec();
+    while body.len() < length {
+        let read = stream.read(&mut buffer).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&buffer[..read]);
+    }
+
+    // multipart uploads, such as documents, are streamed in chunks
+    if headers
+        .get("transfer-encoding")
+        .is_some_and(|encoding| encoding.contains("chunked"))
+    {
+        while !body.ends_with(b"0\r\n\r\n") {
+            let read = stream.read(&mut buffer).await.ok()?;
+            if read == 0 {
+                break;
+            }
+            body.extend_from_slice(&buffer[..read]);
+        }
+
+        body = dechunk(&body);
+    }
+
+    Some(Request {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
@@ -0,0 +1,269 @@
+use super::{serve, Request, Response};
+use callog_bot::timm::modem::Modem;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+const TOKEN: &str = "0123456789abcdef";
+
+#[derive(Default)]
+struct State {
+    pages: HashMap<String, String>,
+    failures: HashMap<String, u16>,
+    delays: HashMap<String, Duration>,
+    down_until: Option<Instant>,
+    reboot_downtime: Duration,
+    reboots: usize,
+    requests: Vec<String>,
+}
+
+/// A TIM modem on localhost, serving the pages in `tests/fixtures/modem` until told otherwise.
+pub struct MockModem {
+    pub url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockModem {
+    pub async fn start() -> Self {
+        let mut state = State {
+            reboot_downtime: Duration::from_millis(300),
+            ..State::default()
+        };
+
+        for page in [
+            "callLog.lp",
+            "home.lp",
+            "tool.lp",
+            "connectedDevices.lp",
+            "wifi.lp",
+            "guestWifi.lp",
+            "portForwarding.lp",
+            "eventLog.lp",
+            "voipStatus.lp",
+        ] {
+            state.pages.insert(page.to_string(), fixture("modem", page));
+        }
+
+        let state = Arc::new(Mutex::new(state));
+        let handler_state = state.clone();
+        let addr = serve(move |request| respond(&handler_state, request)).await;
+
+        MockModem {
+            url: format!("http://{}", addr),
+            state,
+        }
+    }
+
+    /// A modem client that gives up quickly, so slow responses don't slow the tests down.
+    pub fn modem(&self) -> Modem {
+        Modem {
+            timeout: Duration::from_millis(500),
+            ..Modem::new(&self.url)
+        }
+    }
+
+    pub fn set_page(&self, page: &str, html: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.pages.insert(page.to_string(), html.to_string());
+    }
+
+    pub fn fail(&self, page: &str, status: u16) {
+        self.state
+            .lock()
+            .unwrap()
+            .failures
+            .insert(page.to_string(), status);
+    }
+
+    pub fn delay(&self, page: &str, delay: Duration) {
+        self.state
+            .lock()
+            .unwrap()
+            .delays
+            .insert(page.to_string(), delay);
+    }
+
+    pub fn reset(&self, page: &str) {
+        let mut state = self.state.lock().unwrap();
+        state.failures.remove(page);
+        state.delays.remove(page);
+    }
+
+    /// Drops every connection for a while, as when the modem is off or rebooting.
+    pub fn go_down(&self, duration: Duration) {
+        self.state.lock().unwrap().down_until = Some(Instant::now() + duration);
+    }
+
+    pub fn set_reboot_downtime(&self, duration: Duration) {
+        self.state.lock().unwrap().reboot_downtime = duration;
+    }
+
+    pub fn reboots(&self) -> usize {
+        self.state.lock().unwrap().reboots
+    }
+
+    pub fn requests(&self) -> Vec<String> {
+        self.state.lock().unwrap().requests.clone()
+    }
+}
+
+fn respond(state: &Mutex<State>, request: Request) -> Option<Response> {
+    let mut state = state.lock().unwrap();
+    let page = request.page().to_string();
+
+    state
+        .requests
+        .push(format!("{} {}", request.method, request.path));
+
+    if state.down_until.is_some_and(|until| Instant::now() < until) {
+        return None;
+    }
+
+    let delay = state.delays.get(&page).copied().unwrap_or_default();
+
+    if let Some(status) = state.failures.get(&page) {
+        return Some(Response::new(*status, "Errore").delay(delay));
+    }
+
+    if request.method == "POST" && page == "resetAG.lp" {
+        let form = request.text();
+
+        if !form.contains("action=saveRestart") || !form.contains(&format!("rn={}", TOKEN)) {
+            return Some(Response::new(403, "Forbidden"));
+        }
+
+        state.reboots += 1;
+        state.down_until = Some(Instant::now() + state.reboot_downtime);
+
+        return Some(Response::new(200, "Riavvio in corso").delay(delay));
+    }
+
+    if request.method == "POST" && page.to_lowercase().ends_with("wifi.lp") {
+        let form = request.text();
+
+        if !form.contains("action=saveWifi") || !form.contains(&format!("rn={}", TOKEN)) {
+            return Some(Response::new(403, "Forbidden"));
+        }
+
+        let (from, to) = if form.contains("enabled=1") {
+            (">Disattivo<", ">Attivo<")
+        } else {
+            (">Attivo<", ">Disattivo<")
+        };
+        let html = state.pages.get(&page)?.replace(from, to);
+        state.pages.insert(page, html);
+
+        return Some(Response::new(200, "Salvato").delay(delay));
+    }
+
+    if request.method == "POST" && page == "portForwarding.lp" {
+        let form = request.form();
+
+        if form.get("rn").map(String::as_str) != Some(TOKEN) {
+            return Some(Response::new(403, "Forbidden"));
+        }
+
+        let html = state.pages.get(&page)?.clone();
+        let html = match form.get("action").map(String::as_str) {
+            Some("addRule") => html.replacen(
+                "</table>",
+                &format!(
+                    "<tr>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">{}</td>\n</tr>\n</table>",
+                    form.get("name")?,
+                    form.get("protocol")?,
+                    form.get("external")?,
+                    form.get("ip")?,
+                    form.get("internal")?
+                ),
+                1,
+            ),
+            Some("deleteRule") => {
+                let external = format!("<td class=\"fontSize\">{}</td>", form.get("external")?);
+
+                html.split("<tr>")
+                    .filter(|row| row.split('\n').nth(3) != Some(external.as_str()))
+                    .collect::<Vec<&str>>()
+                    .join("<tr>")
+            }
+            _ => return Some(Response::new(400, "Bad request")),
+        };
+        state.pages.insert(page, html);
+
+        return Some(Response::new(200, "Salvato").delay(delay));
+    }
+
+    let html = match state.pages.get(&page) {
+        Some(html) => html,
+        None => return Some(Response::new(404, "Not found")),
+    };
+
+    let mut response = Response::new(200, html)
+        .header("Content-Type", "text/html; charset=utf-8")
+        .delay(delay);
+
+    if page == "tool.lp" {
+        response = response.header("Set-Cookie", &format!("rn={}; Path=/", TOKEN));
+    }
+
+    Some(response)
+}
+
+pub fn fixture(dir: &str, page: &str) -> String {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(dir)
+        .join(page);
+
+    fs::read_to_string(&path).unwrap_or_else(|_| panic!("Missing fixture {}", path.display()))
+}
+
+/// A `callLog.lp` listing incoming calls, newest first, as (number, when, duration).
+pub fn call_log(calls: &[(&str, NaiveDateTime, &str)]) -> String {
+    let rows: String = calls
+        .iter()
+        .map(|(who, when, duration)| {
+            format!(
+                "<tr>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">Linea 1</td>\n<td class=\"fontSize\">Ingresso</td>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">{}</td>\n</tr>\n",
+                who,
+                when.format("%H:%M:%S - %d:%m:%Y"),
+                duration
+            )
+        })
+        .collect();
+
+    format!(
+        "<html><body><table class=\"edittable\">\n<tr><th class=\"fontSize\">Numero</th><th class=\"fontSize\">Linea</th><th class=\"fontSize\">Tipo</th><th class=\"fontSize\">Data e ora</th><th class=\"fontSize\">Durata</th></tr>\n{}</table></body></html>",
+        rows
+    )
+}
+
+/// An `eventLog.lp` with the events, given newest first as the modem lists them.
+pub fn event_log(events: &[(NaiveDateTime, &str, &str)]) -> String {
+    let rows: String = events
+        .iter()
+        .map(|(when, category, message)| {
+            format!(
+                "<tr>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">{}</td>\n<td class=\"fontSize\">{}</td>\n</tr>\n",
+                when.format("%H:%M:%S - %d:%m:%Y"),
+                category,
+                message
+            )
+        })
+        .collect();
+
+    format!(
+        "<html><body><table class=\"edittable\">\n<tr><th class=\"fontSize\">Data e ora</th><th class=\"fontSize\">Categoria</th><th class=\"fontSize\">Messaggio</th></tr>\n{}</table></body></html>",
+        rows
+    )
+}
+
+/// A `home.lp` with the public IP and the line speeds in kbps.
+pub fn home(ip: &str, download: u32, upload: u32) -> String {
+    format!(
+        "<html><body><table class=\"tablecontainttbl\">\n<tr><td class=\"fcolor\">{}</td></tr>\n<tr><td class=\"fcolor\">{} kbps</td></tr>\n<tr><td class=\"fcolor\">{} kbps</td></tr>\n</table></body></html>",
+        ip, download, upload
+    )
+}
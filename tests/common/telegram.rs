@@ -0,0 +1,127 @@
+use super::{serve, Request, Response};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use teloxide::Bot;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SentMessage {
+    pub chat_id: i64,
+    pub text: String,
+    pub silent: bool,
+    pub keyboard: Option<Value>,
+    pub document: Option<String>,
+}
+
+/// Just enough of the Bot API to see what the bot would have said.
+pub struct MockTelegram {
+    pub url: String,
+    messages: Arc<Mutex<Vec<SentMessage>>>,
+}
+
+impl MockTelegram {
+    pub async fn start() -> Self {
+        let messages = Arc::new(Mutex::new(Vec::new()));
+        let handler_messages = messages.clone();
+        let addr = serve(move |request| respond(&handler_messages, request)).await;
+
+        MockTelegram {
+            url: format!("http://{}", addr),
+            messages,
+        }
+    }
+
+    pub fn bot(&self) -> Bot {
+        Bot::new("1234:test").set_api_url(self.url.parse().unwrap())
+    }
+
+    pub fn messages(&self) -> Vec<SentMessage> {
+        self.messages.lock().unwrap().clone()
+    }
+
+    pub fn texts(&self) -> Vec<String> {
+        self.messages()
+            .into_iter()
+            .map(|message| message.text)
+            .collect()
+    }
+}
+
+fn respond(messages: &Mutex<Vec<SentMessage>>, request: Request) -> Option<Response> {
+    let method = request.page().to_lowercase();
+
+    if method.ends_with("/senddocument") {
+        return respond_document(messages, request);
+    }
+
+    let body: Value = serde_json::from_slice(&request.body).unwrap_or(Value::Null);
+
+    if !method.ends_with("/sendmessage") {
+        return Some(Response::new(
+            200,
+            &json!({ "ok": true, "result": true }).to_string(),
+        ));
+    }
+
+    let chat_id = body["chat_id"].as_i64().unwrap_or_default();
+    let text = body["text"].as_str().unwrap_or_default().to_string();
+
+    let mut messages = messages.lock().unwrap();
+    messages.push(SentMessage {
+        chat_id,
+        text: text.clone(),
+        silent: body["disable_notification"].as_bool().unwrap_or(false),
+        keyboard: body.get("reply_markup").cloned(),
+        document: None,
+    });
+
+    let result = json!({
+        "ok": true,
+        "result": {
+            "message_id": messages.len(),
+            "date": 0,
+            "chat": { "id": chat_id, "type": "private", "first_name": "Test" },
+            "text": text,
+        }
+    });
+
+    Some(Response::new(200, &result.to_string()).header("Content-Type", "application/json"))
+}
+
+fn respond_document(messages: &Mutex<Vec<SentMessage>>, request: Request) -> Option<Response> {
+    let form = request.text();
+    let chat_id = form_field(&form, "chat_id")
+        .and_then(|chat_id| chat_id.parse().ok())
+        .unwrap_or_default();
+
+    let mut messages = messages.lock().unwrap();
+    messages.push(SentMessage {
+        chat_id,
+        text: form_field(&form, "caption").unwrap_or_default(),
+        silent: form_field(&form, "disable_notification").as_deref() == Some("true"),
+        keyboard: None,
+        document: form_field(&form, "document")
+            .and_then(|document| form_field(&form, document.trim_start_matches("attach://"))),
+    });
+
+    let result = json!({
+        "ok": true,
+        "result": {
+            "message_id": messages.len(),
+            "date": 0,
+            "chat": { "id": chat_id, "type": "private", "first_name": "Test" },
+            "document": { "file_id": "document", "file_unique_id": "document" },
+        }
+    });
+
+    Some(Response::new(200, &result.to_string()).header("Content-Type", "application/json"))
+}
+
+/// The value of one field of a multipart form.
+fn form_field(form: &str, name: &str) -> Option<String> {
+    let start = form.find(&format!("name=\"{}\"", name))?;
+    let value = &form[start..];
+    let value = &value[value.find("\r\n\r\n")? + 4..];
+    let end = value.find("\r\n--")?;
+
+    Some(value[..end].to_string())
+}
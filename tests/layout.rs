@@ -0,0 +1,81 @@
+mod common;
+
+use callog_bot::bus::Bus;
+use callog_bot::history::{self, Record};
+use callog_bot::monitor::{CallMonitor, SpeedMonitor};
+use callog_bot::notifier::Notifier;
+use common::modem::{fixture, home, MockModem};
+use common::telegram::MockTelegram;
+use teloxide::types::ChatId;
+
+const CHAT_ID: ChatId = ChatId(42);
+
+// a firmware that renamed the table and its cells
+const NEW_CALL_LOG: &str = "<html><body><table class=\"calls\"><tr><th>Numero</th></tr><tr><td class=\"cell\">0612345678</td></tr></table></body></html>";
+
+// one test, as the layout changes already reported are saved together
+#[tokio::test]
+async fn test_layout_changes_are_reported_once() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    modem.set_page("callLog.lp", NEW_CALL_LOG);
+
+    let bus = Bus::new();
+    let mut events = bus.subscribe();
+    let notifier = Notifier::new(modem.modem(), bus.clone());
+    let mut monitor = CallMonitor::new(modem.modem(), bus.clone());
+    assert_eq!(
+        monitor.check().await,
+        Err("couldn't read callLog.lp".to_string())
+    );
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert_eq!(
+        monitor.check().await,
+        Err("couldn't read callLog.lp".to_string())
+    );
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let messages = telegram.messages();
+    assert_eq!(messages.len(), 1);
+    assert!(messages[0].text.contains("callLog.lp"));
+    assert!(messages[0].text.contains("the table is missing"));
+    assert_eq!(messages[0].document.as_deref(), Some(NEW_CALL_LOG));
+
+    // once the page can be read again, a later change is reported again
+    modem.set_page("callLog.lp", &fixture("modem", "callLog.lp"));
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    modem.set_page("callLog.lp", NEW_CALL_LOG);
+    assert_eq!(
+        monitor.check().await,
+        Err("couldn't read callLog.lp".to_string())
+    );
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let documents = telegram
+        .messages()
+        .into_iter()
+        .filter(|message| message.document.is_some())
+        .count();
+    assert_eq!(documents, 2);
+
+    // a home page that can't be read is reported, but isn't an outage
+    modem.set_page(
+        "home.lp",
+        &home("79.12.34.56", 900, 1000).replace("fcolor", "value"),
+    );
+    assert_eq!(
+        SpeedMonitor::new(modem.modem(), bus).check().await,
+        Err("couldn't read home.lp".to_string())
+    );
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let messages = telegram.messages();
+    assert!(messages.last().unwrap().text.contains("home.lp"));
+    assert!(!history::load()
+        .iter()
+        .any(|record| matches!(record, Record::Unreachable { .. })));
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use callog_bot::timm::caller::CallerId;
+use callog_bot::timm::devices::{self, Connection};
+use callog_bot::timm::events::{self, Kind};
+use callog_bot::timm::nat::{self, Forward};
+use callog_bot::timm::stats::LineSpeed;
+use callog_bot::timm::voip::{self, Registration};
+use callog_bot::timm::wifi::{self, Network};
+use callog_bot::timm::{calls, stats, tools};
+use common::modem::MockModem;
+use std::time::Duration;
+use tokio::time::sleep;
+
+#[tokio::test]
+async fn test_download_calls() {
+    let mock = MockModem::start().await;
+
+    let phone_calls = calls::download_calls(&mock.modem()).await.unwrap();
+
+    // the outgoing call isn't listed
+    assert_eq!(phone_calls.len(), 3);
+    assert_eq!(phone_calls[0].who, CallerId::from("0612345678"));
+    assert!(!phone_calls[0].missed);
+    assert_eq!(phone_calls[1].who, CallerId::Anonymous);
+    assert!(phone_calls[1].missed);
+}
+
+#[tokio::test]
+async fn test_download_stats() {
+    let mock = MockModem::start().await;
+
+    let line_stats = stats::download_stats(&mock.modem()).await.unwrap();
+
+    assert_eq!(line_stats.ip, "79.12.34.56");
+    assert_eq!(line_stats.download, 12945);
+    assert_eq!(line_stats.upload, 3143);
+    assert_eq!(line_stats.speed, LineSpeed::Normal);
+}
+
+#[tokio::test]
+async fn test_server_errors_are_failures() {
+    let mock = MockModem::start().await;
+    mock.fail("callLog.lp", 500);
+    mock.fail("home.lp", 503);
+
+    assert_eq!(calls::download_calls(&mock.modem()).await, None);
+    assert_eq!(stats::download_stats(&mock.modem()).await, None);
+
+    mock.reset("home.lp");
+
+    assert!(stats::download_stats(&mock.modem()).await.is_some());
+}
+
+#[tokio::test]
+async fn test_slow_modem_times_out() {
+    let mock = MockModem::start().await;
+    mock.delay("home.lp", Duration::from_secs(2));
+    mock.delay("callLog.lp", Duration::from_millis(100));
+
+    assert_eq!(stats::download_stats(&mock.modem()).await, None);
+    assert!(calls::download_calls(&mock.modem()).await.is_some());
+}
+
+#[tokio::test]
+async fn test_unreachable_modem() {
+    let mock = MockModem::start().await;
+    mock.go_down(Duration::from_secs(5));
+
+    assert_eq!(calls::download_calls(&mock.modem()).await, None);
+    assert_eq!(stats::download_stats(&mock.modem()).await, None);
+    assert!(tools::reboot(&mock.modem()).await.is_none());
+}
+
+#[tokio::test]
+async fn test_reboot_and_downtime() {
+    let mock = MockModem::start().await;
+    mock.set_reboot_downtime(Duration::from_millis(300));
+
+    assert!(tools::reboot(&mock.modem()).await.is_some());
+    assert_eq!(mock.reboots(), 1);
+    assert!(mock.requests().contains(&"POST /resetAG.lp".to_string()));
+
+    assert_eq!(stats::download_stats(&mock.modem()).await, None);
+
+    sleep(Duration::from_millis(400)).await;
+
+    assert!(stats::download_stats(&mock.modem()).await.is_some());
+}
+
+#[tokio::test]
+async fn test_download_devices() {
+    let mock = MockModem::start().await;
+
+    let devices = devices::download_devices(&mock.modem()).await.unwrap();
+
+    assert_eq!(devices.len(), 3);
+    assert_eq!(devices[0].name.as_deref(), Some("desktop"));
+    assert_eq!(devices[0].connection, Connection::Wired);
+    assert_eq!(devices[1].mac, "a4:83:e7:12:34:56");
+    assert_eq!(devices[2].name, None);
+    assert_eq!(devices[2].connection, Connection::WiFi);
+}
+
+#[tokio::test]
+async fn test_download_events() {
+    let mock = MockModem::start().await;
+
+    let events = events::download_events(&mock.modem()).await.unwrap();
+
+    assert_eq!(events.len(), 5);
+    assert_eq!(events[0].kind, Kind::PppUp);
+    assert_eq!(events[1].kind, Kind::DslUp);
+    assert_eq!(events[3].kind, Kind::DslDown);
+    assert_eq!(events[4].kind, Kind::VoipFailure);
+    assert_eq!(
+        events::explain(&events[..4]),
+        Some("PPP session re-established after DSL resync".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_download_lines() {
+    let mock = MockModem::start().await;
+
+    let lines = voip::download_lines(&mock.modem()).await.unwrap();
+
+    assert_eq!(lines.len(), 2);
+    assert_eq!(lines[0].number.as_deref(), Some("0612345678"));
+    assert!(lines[0].is_registered());
+    assert_eq!(lines[1].registration, Registration::Unconfigured);
+}
+
+#[tokio::test]
+async fn test_switch_wifi() {
+    let mock = MockModem::start().await;
+    let modem = mock.modem();
+
+    let status = wifi::download_status(&modem, Network::Main).await.unwrap();
+    assert!(status.enabled);
+    assert_eq!(status.ssid, "TIM-12345678");
+    assert_eq!(status.clients, 4);
+    assert!(
+        !wifi::download_status(&modem, Network::Guest)
+            .await
+            .unwrap()
+            .enabled
+    );
+
+    wifi::set_enabled(&modem, Network::Main, false)
+        .await
+        .unwrap();
+
+    assert!(
+        !wifi::download_status(&modem, Network::Main)
+            .await
+            .unwrap()
+            .enabled
+    );
+    assert!(mock.requests().contains(&"POST /wifi.lp".to_string()));
+}
+
+#[tokio::test]
+async fn test_forward_ports() {
+    let mock = MockModem::start().await;
+    let modem = mock.modem();
+
+    let forwards = nat::download_forwards(&modem).await.unwrap();
+    assert_eq!(forwards.len(), 1);
+    assert_eq!(forwards[0].external, 8443);
+
+    let forward = Forward::try_from("2222 192.168.1.50:22").unwrap();
+    nat::add_forward(&modem, &forward).await.unwrap();
+
+    assert_eq!(
+        nat::download_forwards(&modem).await.unwrap(),
+        vec![forwards[0].clone(), forward]
+    );
+
+    nat::remove_forward(&modem, 2222).await.unwrap();
+
+    assert_eq!(nat::download_forwards(&modem).await.unwrap(), forwards);
+}
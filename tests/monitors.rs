@@ -0,0 +1,610 @@
+mod common;
+
+use callog_bot::bus::{self, Bus};
+use callog_bot::event::Event;
+use callog_bot::history;
+use callog_bot::monitor::{
+    CallMonitor, DeviceMonitor, OutageMonitor, ProbeMonitor, SpeedMonitor, TimerMonitor,
+    VoipMonitor,
+};
+use callog_bot::notifier::Notifier;
+use callog_bot::outage;
+use callog_bot::probe::{self, Rule};
+use callog_bot::rules::RuleSet;
+use callog_bot::spam::Blocklist;
+use callog_bot::store;
+use callog_bot::timers::Timers;
+use callog_bot::timm::nat::{self, Forward};
+use callog_bot::timm::wifi::{self, Network};
+use callog_bot::trusted::TrustedDevices;
+use chrono::{Duration, Local};
+use common::modem::{call_log, event_log, fixture, home, MockModem};
+use common::telegram::MockTelegram;
+use teloxide::types::ChatId;
+use tokio::sync::broadcast::Receiver;
+
+const CHAT_ID: ChatId = ChatId(42);
+
+/// A bus, with the notifier that turns what's published on it into messages.
+fn notifier(modem: &MockModem) -> (Bus, Notifier, Receiver<Event>) {
+    let bus = Bus::new();
+    let events = bus.subscribe();
+
+    (bus.clone(), Notifier::new(modem.modem(), bus), events)
+}
+
+#[tokio::test]
+async fn test_call_monitor_announces_new_calls_once() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+    let now = Local::now().naive_local();
+
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[
+            ("0611111111", now - Duration::minutes(5), "00:01:00"),
+            ("0622222222", now - Duration::hours(3), "00:01:00"),
+        ]),
+    );
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut monitor = CallMonitor::new(modem.modem(), bus);
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    // only the recent call is announced when the bot starts
+    let messages = telegram.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].chat_id, CHAT_ID.0);
+    assert!(messages[0].text.contains("0611111111"));
+    assert!(messages[0].keyboard.is_some());
+
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert_eq!(telegram.messages().len(), 1);
+
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[
+            ("3333333333", now, "00:00:00"),
+            ("0611111111", now - Duration::minutes(5), "00:01:00"),
+            ("0622222222", now - Duration::hours(3), "00:01:00"),
+        ]),
+    );
+
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 2);
+    assert!(texts[1].contains("3333333333"));
+}
+
+#[tokio::test]
+async fn test_call_monitor_records_each_call_once() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let now = Local::now().naive_local();
+
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[
+            ("0611111111", now - Duration::minutes(5), "00:01:00"),
+            ("0622222222", now - Duration::hours(3), "00:01:00"),
+        ]),
+    );
+
+    let mut monitor = CallMonitor::new(modem.modem(), Bus::new());
+    assert_eq!(monitor.check().await, Ok(()));
+    assert_eq!(monitor.check().await, Ok(()));
+    assert_eq!(history::calls().len(), 2);
+
+    // the oldest call is gone from the modem, and a new one arrived
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[
+            ("3333333333", now, "00:00:00"),
+            ("0611111111", now - Duration::minutes(5), "00:01:00"),
+        ]),
+    );
+    assert_eq!(monitor.check().await, Ok(()));
+
+    // as after a restart, which reads the history again
+    let mut monitor = CallMonitor::new(modem.modem(), Bus::new());
+    assert_eq!(monitor.check().await, Ok(()));
+    assert_eq!(history::calls().len(), 3);
+}
+
+#[tokio::test]
+async fn test_call_monitor_escalates_repeated_calls() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+    let now = Local::now().naive_local();
+
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[
+            ("3339876543", now, "00:00:00"),
+            ("3339876543", now - Duration::minutes(4), "00:00:00"),
+            ("3339876543", now - Duration::minutes(8), "00:00:00"),
+        ]),
+    );
+
+    let (bus, mut notifier, mut events) = notifier(&modem);
+    let mut monitor = CallMonitor::new(modem.modem(), bus);
+    notifier.escalation.chats = vec![ChatId(43)];
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let messages = telegram.messages();
+    assert_eq!(messages.len(), 4);
+    assert!(!messages[1].text.starts_with("🚨"));
+    assert_eq!(messages[2].chat_id, CHAT_ID.0);
+    assert!(messages[2].text.starts_with("🚨"));
+    assert!(messages[2].text.contains("Called 3 times in 10 minutes"));
+    assert!(messages[2].keyboard.is_some());
+    assert_eq!(messages[3].chat_id, 43);
+    assert_eq!(messages[3].text, messages[2].text);
+}
+
+#[tokio::test]
+async fn test_call_monitor_follows_alert_rules() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+    let now = Local::now().naive_local();
+
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[
+            ("3391112222", now, "00:00:00"),
+            ("0699998888", now - Duration::minutes(30), "00:01:10"),
+        ]),
+    );
+
+    let (bus, mut notifier, mut events) = notifier(&modem);
+    let mut monitor = CallMonitor::new(modem.modem(), bus);
+    notifier.rules = RuleSet::parse(
+        r#"{"rules": [
+            {"name": "mobiles elsewhere", "on": "new_call", "caller": "+39339*", "actions": [{"notify_chat": 44}]},
+            {"name": "no answered calls", "on": "new_call", "missed": false}
+        ]}"#,
+    )
+    .unwrap();
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let messages = telegram.messages();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].chat_id, 44);
+    assert_eq!(messages[0].text, "📱 3391112222");
+}
+
+#[tokio::test]
+async fn test_call_monitor_drops_blocked_callers() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+    let now = Local::now().naive_local();
+
+    let mut blocklist = Blocklist::load();
+    blocklist.insert("0655555555");
+    blocklist.save().unwrap();
+
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[
+            ("0655555555", now, "00:00:00"),
+            ("0611111111", now - Duration::minutes(5), "00:01:00"),
+        ]),
+    );
+
+    // not even a rule announces them
+    let (bus, mut notifier, mut events) = notifier(&modem);
+    let mut monitor = CallMonitor::new(modem.modem(), bus);
+    notifier.rules = RuleSet::parse(
+        r#"{"rules": [{"name": "landlines", "on": "new_call", "caller": "+3906*", "actions": ["notify"]}]}"#,
+    )
+    .unwrap();
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 1);
+    assert!(texts[0].contains("0611111111"));
+}
+
+#[tokio::test]
+async fn test_speed_monitor_announces_changes() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut monitor = SpeedMonitor::new(modem.modem(), bus);
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(telegram.texts(), vec!["IP is 79.12.34.56".to_string()]);
+
+    modem.set_page("home.lp", &home("79.12.34.56", 900, 1000));
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 2);
+    assert!(texts[1].contains("lower than upload speed"));
+
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert_eq!(telegram.texts().len(), 2);
+
+    let now = history::now();
+    modem.set_page(
+        "eventLog.lp",
+        &event_log(&[
+            (
+                now,
+                "PPP",
+                "Sessione PPP stabilita, indirizzo IP 79.12.34.99",
+            ),
+            (now - Duration::seconds(9), "DSL", "Linea DSL sincronizzata"),
+            (
+                now - Duration::seconds(90),
+                "DSL",
+                "Linea DSL non sincronizzata",
+            ),
+            (now - Duration::hours(3), "PPP", "Sessione PPP terminata"),
+        ]),
+    );
+    modem.set_page("home.lp", &home("79.12.34.99", 900, 1000));
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(
+        telegram.texts()[2],
+        "IP changed to 79.12.34.99 — PPP session re-established after DSL resync".to_string()
+    );
+
+    // the same entries don't explain a later change
+    modem.set_page("home.lp", &home("79.12.34.100", 900, 1000));
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(telegram.texts()[3], "IP is 79.12.34.100".to_string());
+}
+
+#[tokio::test]
+async fn test_speed_monitor_ignores_old_events() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+    let now = history::now();
+
+    // the last change was explained yesterday
+    store::save(
+        "speed_monitor.json",
+        &serde_json::json!({
+            "last_speed": "Normal",
+            "last_ip": "79.12.34.56",
+            "last_event": now - Duration::days(1),
+        }),
+    )
+    .unwrap();
+    modem.set_page(
+        "eventLog.lp",
+        &event_log(&[(
+            now - Duration::hours(2),
+            "PPP",
+            "Sessione PPP stabilita, indirizzo IP 79.12.34.99",
+        )]),
+    );
+    modem.set_page("home.lp", &home("79.12.34.99", 12945, 3143));
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut monitor = SpeedMonitor::resume(modem.modem(), bus);
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(telegram.texts(), vec!["IP is 79.12.34.99".to_string()]);
+}
+
+#[tokio::test]
+async fn test_rules_reboot_a_slow_line_once_in_a_while() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+    modem.set_reboot_downtime(std::time::Duration::ZERO);
+    modem.set_page("home.lp", &home("79.12.34.56", 1500, 900));
+
+    let (bus, mut notifier, mut events) = notifier(&modem);
+    let mut monitor = SpeedMonitor::new(modem.modem(), bus);
+    notifier.rules = RuleSet::parse(
+        r#"{"rules": [{"name": "crawling", "on": "line_sample", "max_download": 2000, "actions": ["reboot"]}]}"#,
+    )
+    .unwrap();
+
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert_eq!(modem.reboots(), 1);
+
+    // still slow at the next sample, but the modem only just rebooted
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert_eq!(modem.reboots(), 1);
+    assert_eq!(
+        telegram
+            .texts()
+            .iter()
+            .filter(|text| text.starts_with("🔄"))
+            .count(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_monitors_stay_quiet_when_modem_is_down() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    modem.go_down(std::time::Duration::from_secs(5));
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    assert_eq!(
+        CallMonitor::new(modem.modem(), bus.clone()).check().await,
+        Err("couldn't download callLog.lp".to_string())
+    );
+    assert_eq!(
+        SpeedMonitor::new(modem.modem(), bus).check().await,
+        Err("couldn't download home.lp".to_string())
+    );
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert!(telegram.messages().is_empty());
+}
+
+#[tokio::test]
+async fn test_outage_monitor_tells_modem_from_internet() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut others = bus.subscribe();
+    let mut monitor = OutageMonitor::new(modem.modem(), bus);
+    monitor.host = "localhost".to_string();
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert!(telegram.messages().is_empty());
+
+    modem.go_down(std::time::Duration::from_secs(5));
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 1);
+    assert!(texts[0].starts_with("📡 Modem unreachable since"));
+
+    // the other subscribers hear about the modem once per outage
+    assert!(matches!(
+        bus::try_next(&mut others),
+        Some(Event::ModemUnreachable { .. })
+    ));
+    assert!(matches!(
+        bus::try_next(&mut others),
+        Some(Event::OutageStarted(_))
+    ));
+    assert_eq!(bus::try_next(&mut others), None);
+
+    // the modem is back, but without a public IP
+    modem.go_down(std::time::Duration::ZERO);
+    modem.set_page("home.lp", &home("0.0.0.0", 0, 1));
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 3);
+    assert!(texts[1].starts_with("✅ Back after"));
+    assert!(texts[2].starts_with("🌐 Internet down since"));
+
+    modem.set_page("home.lp", &home("79.12.34.56", 12945, 3143));
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(telegram.texts().len(), 4);
+    assert_eq!(
+        outage::load_since(history::now() - Duration::hours(1)).len(),
+        2
+    );
+    assert_eq!(outage::current(), None);
+}
+
+#[tokio::test]
+async fn test_probe_monitor_announces_poor_connection() {
+    let _data_dir = common::init();
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let up = listener.local_addr().unwrap();
+    // nothing listens on a port just given back
+    let down = tokio::net::TcpListener::bind("127.0.0.1:0")
+        .await
+        .unwrap()
+        .local_addr()
+        .unwrap();
+
+    let modem = MockModem::start().await;
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut monitor = ProbeMonitor::new(&modem.modem(), bus);
+    monitor.prober.targets = probe::parse_targets(&format!("local=tcp:{}", down));
+    monitor.prober.attempts = 2;
+    monitor.rule = Rule {
+        lasting: Duration::zero(),
+        ..Rule::default()
+    };
+
+    monitor.check().await;
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 1);
+    assert!(texts[0].contains("Poor connection"));
+    assert!(texts[0].contains("local unreachable"));
+
+    monitor.prober.targets = probe::parse_targets(&format!("local=tcp:{}", up));
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 2);
+    assert!(texts[1].starts_with("✅ Connection back to normal: local"));
+}
+
+#[tokio::test]
+async fn test_device_monitor_announces_unknown_devices() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    // the devices there at the start are learnt quietly, even after a /trust
+    let mut trusted = TrustedDevices::load();
+    trusted.trust("66:55:44:33:22:11", "Printer").unwrap();
+    trusted.save().unwrap();
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut monitor = DeviceMonitor::new(modem.modem(), bus);
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert!(telegram.messages().is_empty());
+
+    let page = fixture("modem", "connectedDevices.lp").replace(
+        "</table>",
+        "<tr><td class=\"fontSize\">tablet</td><td class=\"fontSize\">11:22:33:44:55:66</td><td class=\"fontSize\">192.168.1.77</td><td class=\"fontSize\">Wi-Fi</td></tr>\n<tr><td class=\"fontSize\">printer</td><td class=\"fontSize\">66:55:44:33:22:11</td><td class=\"fontSize\">192.168.1.78</td><td class=\"fontSize\">Ethernet</td></tr>\n</table>",
+    );
+    modem.set_page("connectedDevices.lp", &page);
+
+    assert_eq!(monitor.check().await, Ok(()));
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    let texts = telegram.texts();
+    assert_eq!(texts.len(), 1);
+    assert!(texts[0].contains("tablet (11:22:33:44:55:66, 192.168.1.77)"));
+    assert!(texts[0].contains("/trust 11:22:33:44:55:66"));
+}
+
+#[tokio::test]
+async fn test_voip_monitor_announces_registration_changes() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut monitor = VoipMonitor::new(modem.modem(), bus);
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert!(telegram.messages().is_empty());
+
+    let page = fixture("modem", "voipStatus.lp");
+    modem.set_page(
+        "voipStatus.lp",
+        &page.replace(">Registrata<", ">Non registrata<"),
+    );
+    assert_eq!(monitor.check().await, Ok(()));
+    assert_eq!(monitor.check().await, Ok(()));
+
+    modem.set_page("voipStatus.lp", &page);
+    assert_eq!(monitor.check().await, Ok(()));
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(
+        telegram.texts(),
+        vec![
+            "☎️ Linea 1 (0612345678) is no longer registered: calls won't come through."
+                .to_string(),
+            "✅ Linea 1 (0612345678) registered again.".to_string(),
+        ]
+    );
+}
+
+// one test, as the timers are saved together
+#[tokio::test]
+async fn test_timer_monitor_undoes_timed_settings() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+
+    wifi::set_enabled(&modem.modem(), Network::Guest, true)
+        .await
+        .unwrap();
+
+    let mut timers = Timers::load();
+    timers.set_wifi(Network::Guest, false, history::now() + Duration::hours(2));
+    timers.save().unwrap();
+
+    let (bus, notifier, mut events) = notifier(&modem);
+    let mut monitor = TimerMonitor::new(modem.modem(), bus.clone());
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+    assert!(telegram.messages().is_empty());
+
+    let mut timers = Timers::load();
+    timers.set_wifi(Network::Guest, false, history::now() - Duration::minutes(1));
+    timers.save().unwrap();
+
+    monitor.check().await;
+    monitor.check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(
+        telegram.texts(),
+        vec!["📴 Guest Wi-Fi switched off as planned.".to_string()]
+    );
+    assert!(
+        !wifi::download_status(&modem.modem(), Network::Guest)
+            .await
+            .unwrap()
+            .enabled
+    );
+
+    let forward = Forward::try_from("2222 192.168.1.50:22").unwrap();
+    nat::add_forward(&modem.modem(), &forward).await.unwrap();
+
+    let mut timers = Timers::load();
+    timers.set_port(2222, history::now() - Duration::minutes(1));
+    timers.save().unwrap();
+
+    // a monitor started after the port was opened, as after a restart
+    TimerMonitor::new(modem.modem(), bus).check().await;
+    notifier.drain(&bot, CHAT_ID, &mut events).await;
+
+    assert_eq!(
+        telegram.texts()[1],
+        "🔒 Port 2222 closed as planned.".to_string()
+    );
+    assert!(nat::download_forwards(&modem.modem())
+        .await
+        .unwrap()
+        .iter()
+        .all(|forward| forward.external != 2222));
+    assert_eq!(Timers::load().port(2222), None);
+}
@@ -0,0 +1,76 @@
+use callog_bot::timm::{calls, stats};
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Each directory in `tests/fixtures/corpus` holds pages saved from one modem,
+/// next to the JSON of what the parsers made of them.
+fn corpus() -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/corpus");
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.is_dir())
+        .collect();
+
+    entries.sort();
+    entries
+}
+
+/// Compares `parsed` with the snapshot, or rewrites the snapshot when `UPDATE_SNAPSHOTS` is set.
+fn check_snapshot<T: Serialize>(path: &Path, parsed: &T) -> Option<String> {
+    let json = serde_json::to_string_pretty(parsed).unwrap() + "\n";
+
+    if env::var("UPDATE_SNAPSHOTS").is_ok() {
+        fs::write(path, &json).unwrap();
+        return None;
+    }
+
+    match fs::read_to_string(path) {
+        Ok(snapshot) if snapshot == json => None,
+        Ok(snapshot) => Some(format!(
+            "{} changed\n--- expected\n{}--- parsed\n{}",
+            path.display(),
+            snapshot,
+            json
+        )),
+        Err(_) => Some(format!(
+            "{} is missing, run with UPDATE_SNAPSHOTS=1 to create it",
+            path.display()
+        )),
+    }
+}
+
+#[test]
+fn test_corpus_calls() {
+    let failures: Vec<String> = corpus()
+        .iter()
+        .filter_map(|dir| {
+            let html = fs::read_to_string(dir.join("callLog.lp")).unwrap();
+            let phone_calls = calls::parse_calls(&html).unwrap();
+
+            assert!(!phone_calls.is_empty(), "No calls in {}", dir.display());
+
+            check_snapshot(&dir.join("calls.json"), &phone_calls)
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
+
+#[test]
+fn test_corpus_stats() {
+    let failures: Vec<String> = corpus()
+        .iter()
+        .filter_map(|dir| {
+            let html = fs::read_to_string(dir.join("home.lp")).unwrap();
+            let line_stats = stats::parse_stats(&html)
+                .unwrap_or_else(|| panic!("No stats in {}", dir.display()));
+
+            check_snapshot(&dir.join("stats.json"), &line_stats)
+        })
+        .collect();
+
+    assert!(failures.is_empty(), "{}", failures.join("\n"));
+}
@@ -0,0 +1,52 @@
+mod common;
+
+use callog_bot::speedtest::Endpoint;
+use common::{serve, Response};
+use std::sync::{Arc, Mutex};
+
+#[tokio::test]
+async fn test_speedtest_against_local_endpoint() {
+    let uploaded = Arc::new(Mutex::new(0));
+    let handler_uploaded = uploaded.clone();
+
+    let addr = serve(move |request| match request.page() {
+        "__down" => {
+            let bytes: usize = request
+                .path
+                .split("bytes=")
+                .nth(1)
+                .and_then(|bytes| bytes.parse().ok())
+                .unwrap_or(0);
+
+            Some(Response::new(200, &"0".repeat(bytes)))
+        }
+        "__up" => {
+            *handler_uploaded.lock().unwrap() = request.body.len();
+            Some(Response::new(200, "ok"))
+        }
+        _ => Some(Response::new(404, "Not found")),
+    })
+    .await;
+
+    let endpoint = Endpoint {
+        bytes: 100_000,
+        ..Endpoint::new(&format!("http://{}/", addr))
+    };
+    let speed_test = endpoint.run(chrono::Local::now().naive_local()).await;
+
+    assert!(speed_test.download.is_some_and(|kbps| kbps > 0));
+    assert!(speed_test.upload.is_some_and(|kbps| kbps > 0));
+    assert_eq!(*uploaded.lock().unwrap(), 100_000);
+}
+
+#[tokio::test]
+async fn test_speedtest_endpoint_down() {
+    let addr = serve(|_request| Some(Response::new(503, "Busy"))).await;
+
+    let speed_test = Endpoint::new(&format!("http://{}", addr))
+        .run(chrono::Local::now().naive_local())
+        .await;
+
+    assert_eq!(speed_test.download, None);
+    assert_eq!(speed_test.upload, None);
+}
@@ -0,0 +1,97 @@
+mod common;
+
+use callog_bot::bus::{self, Bus};
+use callog_bot::event::Event;
+use callog_bot::monitor::{CallMonitor, SpeedMonitor};
+use callog_bot::supervisor::supervise;
+use chrono::{Duration, Local};
+use common::modem::{call_log, MockModem};
+use common::telegram::MockTelegram;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use teloxide::types::ChatId;
+use tokio::time::timeout;
+
+const CHAT_ID: ChatId = ChatId(42);
+
+#[tokio::test]
+async fn test_crashes_are_reported_and_restarted_later() {
+    let _data_dir = common::init();
+    let telegram = MockTelegram::start().await;
+    let bot = telegram.bot();
+    let runs = AtomicUsize::new(0);
+
+    // the first restart waits a few seconds, longer than the test
+    let supervised = supervise("monitor_test", &bot, CHAT_ID, || async {
+        runs.fetch_add(1, Ordering::SeqCst);
+        panic!("no modem");
+    });
+    assert!(timeout(std::time::Duration::from_secs(1), supervised)
+        .await
+        .is_err());
+
+    assert_eq!(runs.load(Ordering::SeqCst), 1);
+    assert_eq!(
+        telegram.texts(),
+        vec!["💥 monitor_test crashed (1 in a row): no modem\nRestarting in 5s.".to_string()]
+    );
+}
+
+#[tokio::test]
+async fn test_monitors_resume_where_they_stopped() {
+    let _data_dir = common::init();
+    let modem = MockModem::start().await;
+    let now = Local::now().naive_local();
+
+    modem.set_page(
+        "callLog.lp",
+        &call_log(&[("0611111111", now - Duration::minutes(5), "00:01:00")]),
+    );
+
+    let bus = Bus::new();
+    let mut events = bus.subscribe();
+    assert_eq!(
+        CallMonitor::resume(modem.modem(), bus.clone())
+            .check()
+            .await,
+        Ok(())
+    );
+    assert_eq!(
+        SpeedMonitor::resume(modem.modem(), bus.clone())
+            .check()
+            .await,
+        Ok(())
+    );
+
+    assert!(matches!(
+        bus::try_next(&mut events),
+        Some(Event::NewCall(_))
+    ));
+    assert!(matches!(
+        bus::try_next(&mut events),
+        Some(Event::LineSample(_))
+    ));
+    assert!(matches!(
+        bus::try_next(&mut events),
+        Some(Event::IpChanged { .. })
+    ));
+
+    // started again, they know the call and the line already
+    assert_eq!(
+        CallMonitor::resume(modem.modem(), bus.clone())
+            .check()
+            .await,
+        Ok(())
+    );
+    assert_eq!(
+        SpeedMonitor::resume(modem.modem(), bus.clone())
+            .check()
+            .await,
+        Ok(())
+    );
+
+    assert!(matches!(
+        bus::try_next(&mut events),
+        Some(Event::LineSample(_))
+    ));
+    assert_eq!(bus::try_next(&mut events), None);
+}